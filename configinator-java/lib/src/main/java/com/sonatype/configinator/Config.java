@@ -11,6 +11,7 @@ public class Config implements AutoCloseable {
 
     private static native long loadConfigFromFile(String filePath);
     private static native long loadConfigFromFolder(String filePath);
+    private static native long loadConfigFromFolderWithProfile(String filePath, String profile);
     private static native void unloadConfig(long configPointer);
     private static native String configGetSetup(long configPointer);
     private static native String configGetBuild(long configPointer);
@@ -25,6 +26,10 @@ public class Config implements AutoCloseable {
     private static native Integer configGetAndroidVersion(long configPointer);
     private static native List<String> configGetErrorproneBugPatterns(long configPointer);
     private static native Boolean configGetSummaryComments(long configPointer);
+    private static native boolean configIsFileIgnored(long configPointer, String relativePath);
+    private static native String configClassifyRule(long configPointer, String ruleId);
+    private static native List<Diagnostic> configValidate(long configPointer);
+    private static native Origin configGetOrigin(long configPointer, String key);
 
     static {
         System.loadLibrary("configinator_jni");
@@ -54,6 +59,15 @@ public class Config implements AutoCloseable {
         return config;
     }
 
+    public static Config loadFromFolder(Path filePath, String profile) throws RuntimeException {
+        var config = new Config();
+        config.configPointer = loadConfigFromFolderWithProfile(filePath.toString(), profile);
+        if (0 == config.configPointer) {
+            throw new ConfigurationFailedToLoadException(filePath);
+        }
+        return config;
+    }
+
     public String getSetup() {
         assertConfigLoaded();
         return configGetSetup(configPointer);
@@ -119,6 +133,33 @@ public class Config implements AutoCloseable {
         return configGetSummaryComments(configPointer);
     }
 
+    // the path must be relative to the repository root, as the ignoreFiles patterns are
+    public boolean isFileIgnored(Path relativePath) {
+        assertConfigLoaded();
+        return configIsFileIgnored(configPointer, relativePath.toString());
+    }
+
+    public RuleDisposition classifyRule(String ruleId) {
+        assertConfigLoaded();
+        return RuleDisposition.valueOf(configClassifyRule(configPointer, ruleId));
+    }
+
+    // the key is named as in configuration files, such as "jdk11", and null is returned if it is not set
+    public Origin getOrigin(String key) {
+        assertConfigLoaded();
+        return configGetOrigin(configPointer, key);
+    }
+
+    // an analysis should not be started if any of the diagnostics are errors
+    public List<Diagnostic> validate() {
+        assertConfigLoaded();
+        return configValidate(configPointer);
+    }
+
+    public boolean isValid() {
+        return validate().stream().noneMatch(d -> d.getSeverity() == Severity.ERROR);
+    }
+
     private void assertConfigLoaded() {
         if (0 == configPointer) {
             throw new ConfigurationUsedAfterCleanupException();
@@ -0,0 +1,39 @@
+package com.sonatype.configinator;
+
+// mirrors configinator::Diagnostic, constructed by the JNI layer
+public class Diagnostic {
+
+    private final Severity severity;
+    private final String code;
+    private final String key;
+    private final String message;
+
+    Diagnostic(String severity, String code, String key, String message) {
+        this.severity = Severity.valueOf(severity);
+        this.code = code;
+        this.key = key;
+        this.message = message;
+    }
+
+    public Severity getSeverity() {
+        return severity;
+    }
+
+    // stable identifier of the check, such as "android-version-out-of-range"
+    public String getCode() {
+        return code;
+    }
+
+    public String getKey() {
+        return key;
+    }
+
+    public String getMessage() {
+        return message;
+    }
+
+    @Override
+    public String toString() {
+        return severity.name().toLowerCase() + "[" + code + "]: " + message;
+    }
+}
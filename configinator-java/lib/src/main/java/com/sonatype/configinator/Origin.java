@@ -0,0 +1,61 @@
+package com.sonatype.configinator;
+
+// mirrors configinator::Origin, constructed by the JNI layer
+public class Origin {
+
+    public enum Kind {
+        FILE,
+        IGNORES_FILE,
+        ENVIRONMENT,
+    }
+
+    private final Kind kind;
+    private final String path;
+    private final int line;
+    private final int column;
+    private final String variable;
+
+    Origin(String kind, String path, int line, int column, String variable) {
+        this.kind = Kind.valueOf(kind);
+        this.path = path;
+        this.line = line;
+        this.column = column;
+        this.variable = variable;
+    }
+
+    public Kind getKind() {
+        return kind;
+    }
+
+    // null for environment variables
+    public String getPath() {
+        return path;
+    }
+
+    // 1-based, or 0 when the position in the file is not known
+    public int getLine() {
+        return line;
+    }
+
+    // 1-based, or 0 when the position in the file is not known
+    public int getColumn() {
+        return column;
+    }
+
+    // only set for environment variables
+    public String getVariable() {
+        return variable;
+    }
+
+    @Override
+    public String toString() {
+        switch (kind) {
+            case ENVIRONMENT:
+                return "environment variable " + variable;
+            case IGNORES_FILE:
+                return "ignores file " + path;
+            default:
+                return line == 0 ? path : path + ":" + line + ":" + column;
+        }
+    }
+}
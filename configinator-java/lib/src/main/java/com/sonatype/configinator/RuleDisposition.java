@@ -0,0 +1,8 @@
+package com.sonatype.configinator;
+
+// mirrors configinator::RuleDisposition
+public enum RuleDisposition {
+    IGNORED,
+    IMPORTANT,
+    NORMAL,
+}
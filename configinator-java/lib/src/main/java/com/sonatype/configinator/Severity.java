@@ -0,0 +1,8 @@
+package com.sonatype.configinator;
+
+// mirrors configinator::Severity
+public enum Severity {
+    ERROR,
+    WARNING,
+    INFO,
+}
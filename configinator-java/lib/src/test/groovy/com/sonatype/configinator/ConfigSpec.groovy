@@ -54,6 +54,56 @@ class ConfigSpec extends Specification {
         subject.getSummaryComments()
     }
 
+    def 'it should check files against the ignored files'() {
+        given: 'a loaded configuration'
+        def subject = Config.loadFromFile(Path.of('src/test/resources/examples/.lift.toml'))
+
+        expect: 'only matching files are ignored'
+        subject.isFileIgnored(Path.of('python/requirements.txt'))
+        !subject.isFileIgnored(Path.of('python/setup.py'))
+    }
+
+    def 'it should classify rules'() {
+        given: 'a loaded configuration'
+        def subject = Config.loadFromFile(Path.of('src/test/resources/examples/.lift.toml'))
+
+        expect: 'rules are classified by the important and ignored rules'
+        subject.classifyRule('rule A') == RuleDisposition.IMPORTANT
+        subject.classifyRule('rule 2') == RuleDisposition.IGNORED
+        subject.classifyRule('rule 3') == RuleDisposition.NORMAL
+    }
+
+    def 'it should report where values came from'() {
+        given: 'a loaded configuration'
+        def subject = Config.loadFromFile(Path.of('src/test/resources/examples/.lift.toml'))
+
+        when: 'the origin of a value is requested'
+        def origin = subject.getOrigin('jdk11')
+
+        then: 'the line of the file is reported'
+        origin.kind == Origin.Kind.FILE
+        origin.path == 'src/test/resources/examples/.lift.toml'
+        origin.line == 10
+        origin.column == 1
+        subject.getOrigin('unknownKey') == null
+    }
+
+    def 'it should validate a config'() {
+        given: 'a configuration with an impossible android version'
+        def subject = Config.loadFromFile(Path.of('src/test/resources/examples/invalid_values/.lift.toml'))
+
+        when: 'the config is validated'
+        def diagnostics = subject.validate()
+
+        then: 'the android version is reported as an error'
+        diagnostics.size() == 1
+        diagnostics[0].severity == Severity.ERROR
+        diagnostics[0].code == 'android-version-out-of-range'
+        diagnostics[0].key == 'androidVersion'
+        !subject.isValid()
+        Config.loadFromFile(Path.of('src/test/resources/examples/.lift.toml')).isValid()
+    }
+
     def 'it should load a default config when none exists'() {
         given: 'a path to a configuration'
         def path = Path.of('src/test/resources/examples/no_configs/')
@@ -91,15 +141,52 @@ class ConfigSpec extends Specification {
         e.message == 'Attempted to use a configuration after it was cleaned up'
     }
 
+    def 'it should load a json config'() {
+        given: 'a path to a json configuration'
+        def path = Path.of('src/test/resources/examples/.lift.json')
+
+        when: 'the file is loaded'
+        def subject = Config.loadFromFile(path)
+
+        then: 'the config is available'
+        subject.getSetup() == /echo 'Hello, Lift'/
+        subject.getAllow() == ['amy']
+        subject.getSummaryComments()
+    }
+
     def 'it should handle exceptions thrown from the Rust code'() {
         given: 'a path to invalid configuration'
-        def path = Path.of('src/test/resources/examples/.lift.json')
+        def path = Path.of('src/test/resources/examples/invalid/.lift.toml')
 
         when: 'the file is loaded'
         Config.loadFromFile(path)
 
         then: 'an exception is thrown'
         def e = thrown(JNIException)
-        e.message == 'Failed to parse file as a toml file'
+        e.message.startsWith('Failed to parse file as a toml file at src/test/resources/examples/invalid/.lift.toml:1:1: ')
+    }
+
+    def 'it should load a config with a profile'() {
+        given: 'a path to a folder whose configuration has profiles'
+        def path = Path.of('src/test/resources/examples/profiles/')
+
+        when: 'the folder is loaded with a profile'
+        def subject = Config.loadFromFolder(path, 'nightly')
+
+        then: 'the profile replaces the top-level settings'
+        subject.getBuild() == 'make'
+        subject.getTools() == ['infer', 'eslint', 'errorprone']
+    }
+
+    def 'it should list the available profiles when the profile is unknown'() {
+        given: 'a path to a folder whose configuration has profiles'
+        def path = Path.of('src/test/resources/examples/profiles/')
+
+        when: 'the folder is loaded with an unknown profile'
+        Config.loadFromFolder(path, 'weekly')
+
+        then: 'an exception is thrown'
+        def e = thrown(JNIException)
+        e.message == 'Unknown profile `weekly`, the available profiles are `nightly`, `pr`'
     }
 }
@@ -1,17 +1,31 @@
-use configinator::{error::ConfigError, Config};
+//! JNI bindings for `com.sonatype.configinator.Config`.
+//!
+//! The `unsafe` entry points all take a `config_pointer` that the Java wrapper only passes while
+//! the configuration is loaded.
+
+use configinator::{
+    error::ConfigError, Diagnostic, LoadedConfig, Origin, RuleDisposition, Severity,
+};
 use jni::objects::{JClass, JObject, JString, JValue};
-use jni::sys::{jlong, jobject, jstring};
+use jni::sys::{jboolean, jlong, jobject, jstring, JNI_FALSE};
 use jni::JNIEnv;
 
 mod error;
 use error::{throw_exception, JniError};
 
-const BOOLEAN_CLASS: &'static str = "java/lang/Boolean";
-const BOOLEAN_CONSTRUCTOR: &'static str = "(Z)V";
-const INTEGER_CLASS: &'static str = "java/lang/Integer";
-const INTEGER_CONSTRUCTOR: &'static str = "(I)V";
-const ARRAYLIST_CLASS: &'static str = "java/util/ArrayList";
-const ARRAYLIST_CONSTRUCTOR: &'static str = "()V";
+const BOOLEAN_CLASS: &str = "java/lang/Boolean";
+const BOOLEAN_CONSTRUCTOR: &str = "(Z)V";
+const INTEGER_CLASS: &str = "java/lang/Integer";
+const INTEGER_CONSTRUCTOR: &str = "(I)V";
+const ARRAYLIST_CLASS: &str = "java/util/ArrayList";
+const ARRAYLIST_CONSTRUCTOR: &str = "()V";
+/// Class defined in configinator-java/lib/src/main/java/com/sonatype/configinator/Diagnostic.java
+const DIAGNOSTIC_CLASS: &str = "com/sonatype/configinator/Diagnostic";
+const DIAGNOSTIC_CONSTRUCTOR: &str =
+    "(Ljava/lang/String;Ljava/lang/String;Ljava/lang/String;Ljava/lang/String;)V";
+/// Class defined in configinator-java/lib/src/main/java/com/sonatype/configinator/Origin.java
+const ORIGIN_CLASS: &str = "com/sonatype/configinator/Origin";
+const ORIGIN_CONSTRUCTOR: &str = "(Ljava/lang/String;Ljava/lang/String;IILjava/lang/String;)V";
 
 #[no_mangle]
 pub extern "system" fn Java_com_sonatype_configinator_Config_loadConfigFromFile(
@@ -22,7 +36,7 @@ pub extern "system" fn Java_com_sonatype_configinator_Config_loadConfigFromFile(
     let config_path = env.get_string(config_path);
     match config_path {
         Ok(config_path) => {
-            let config = Config::from_file::<String>(config_path.into());
+            let config = LoadedConfig::from_file::<String>(config_path.into());
             match config {
                 Ok(config) => Box::into_raw(Box::new(config)) as jlong,
                 Err(ConfigError::FileNotFound(_e)) => JObject::null().into_inner() as jlong,
@@ -51,10 +65,10 @@ pub extern "system" fn Java_com_sonatype_configinator_Config_loadConfigFromFolde
     let config_path = env.get_string(config_path);
     match config_path {
         Ok(config_path) => {
-            let config = Config::from_folder::<String>(config_path.into());
+            let config = LoadedConfig::from_folder::<String>(config_path.into());
             match config {
                 Ok(Some(config)) => Box::into_raw(Box::new(config)) as jlong,
-                Ok(None) => Box::into_raw(Box::new(Config::default())) as jlong,
+                Ok(None) => Box::into_raw(Box::new(LoadedConfig::default())) as jlong,
                 Err(ConfigError::FileNotFound(_e)) => JObject::null().into_inner() as jlong,
                 Err(e) => {
                     throw_exception(&env, &e);
@@ -72,22 +86,68 @@ pub extern "system" fn Java_com_sonatype_configinator_Config_loadConfigFromFolde
     }
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_sonatype_configinator_Config_loadConfigFromFolderWithProfile(
+    env: JNIEnv,
+    _class: JClass,
+    config_path: JString,
+    profile: JString,
+) -> jlong {
+    let strings = env.get_string(config_path).and_then(|config_path| {
+        env.get_string(profile)
+            .map(|profile| (String::from(config_path), String::from(profile)))
+    });
+    match strings {
+        Ok((config_path, profile)) => {
+            let config = LoadedConfig::from_folder(config_path)
+                .map(Option::unwrap_or_default)
+                .and_then(|config| config.with_profile(&profile));
+            match config {
+                Ok(config) => Box::into_raw(Box::new(config)) as jlong,
+                Err(ConfigError::FileNotFound(_e)) => JObject::null().into_inner() as jlong,
+                Err(e) => {
+                    throw_exception(&env, &e);
+                    JObject::null().into_inner() as jlong
+                }
+            }
+        }
+        Err(e) => {
+            throw_exception(
+                &env,
+                &format!(
+                    "Could not process the config path or profile as a string:\n{}",
+                    e
+                ),
+            );
+            JObject::null().into_inner() as jlong
+        }
+    }
+}
+
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`. It must not be used again afterwards.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_unloadConfig(
     _env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) {
-    let _boxed_config = Box::from_raw(config_pointer as *mut Config);
+    let _boxed_config = Box::from_raw(config_pointer as *mut LoadedConfig);
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetSetup(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jstring {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(setup) = &config.setup {
         let output = env.new_string(setup.clone());
@@ -106,13 +166,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetSet
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetBuild(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jstring {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(build) = &config.build {
         let output = env.new_string(build.clone());
@@ -131,13 +195,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetBui
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetImportantRules(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(important_rules) = &config.important_rules {
         let output = create_list_of_strings(&env, important_rules);
@@ -159,13 +227,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetImp
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetIgnoreRules(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(ignore_rules) = &config.ignore_rules {
         let output = create_list_of_strings(&env, ignore_rules);
@@ -187,13 +259,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetIgn
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetIgnoreFiles(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jstring {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(ignore_files) = &config.ignore_files {
         let output = env.new_string(ignore_files.clone());
@@ -212,13 +288,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetIgn
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetTools(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(tools) = &config.tools {
         let output = create_list_of_strings(&env, tools);
@@ -237,13 +317,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetToo
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetDisableTools(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(disable_tools) = &config.disable_tools {
         let output = create_list_of_strings(&env, disable_tools);
@@ -265,13 +349,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetDis
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetCustomTools(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(custom_tools) = &config.custom_tools {
         let output = create_list_of_strings(&env, custom_tools);
@@ -293,13 +381,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetCus
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetAllow(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(allow) = &config.allow {
         let output = create_list_of_strings(&env, allow);
@@ -318,13 +410,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetAll
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetJdk11(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(jdk_11) = &config.jdk_11 {
         let output = env.new_object(BOOLEAN_CLASS, BOOLEAN_CONSTRUCTOR, &[JValue::from(*jdk_11)]);
@@ -343,13 +439,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetJdk
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetAndroidVersion(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(android_version) = &config.android_version {
         let output = env.new_object(
@@ -375,13 +475,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetAnd
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetErrorproneBugPatterns(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(errorprone_bug_patterns) = &config.errorprone_bug_patterns {
         let list = create_list_of_strings(&env, errorprone_bug_patterns);
@@ -403,13 +507,17 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetErr
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
 #[no_mangle]
 pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetSummaryComments(
     env: JNIEnv,
     _class: JClass,
     config_pointer: jlong,
 ) -> jobject {
-    let config = &mut *(config_pointer as *mut Config);
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
 
     if let Some(summary_comments) = &config.summary_comments {
         let output = env.new_object(
@@ -435,6 +543,202 @@ pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetSum
     }
 }
 
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configIsFileIgnored(
+    env: JNIEnv,
+    _class: JClass,
+    config_pointer: jlong,
+    relative_path: JString,
+) -> jboolean {
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
+
+    match env.get_string(relative_path) {
+        Ok(relative_path) => {
+            let relative_path: String = relative_path.into();
+            config.is_file_ignored(relative_path) as jboolean
+        }
+        Err(e) => {
+            throw_exception(
+                &env,
+                &format!("Could not process the relative path as a string:\n{}", e),
+            );
+            JNI_FALSE
+        }
+    }
+}
+
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configClassifyRule(
+    env: JNIEnv,
+    _class: JClass,
+    config_pointer: jlong,
+    rule_id: JString,
+) -> jstring {
+    let config = &mut (*(config_pointer as *mut LoadedConfig)).config;
+
+    let rule_id: String = match env.get_string(rule_id) {
+        Ok(rule_id) => rule_id.into(),
+        Err(e) => {
+            throw_exception(
+                &env,
+                &format!("Could not process the rule id as a string:\n{}", e),
+            );
+            return JObject::null().into_inner();
+        }
+    };
+    // names of the constants in com.sonatype.configinator.RuleDisposition
+    let disposition = match config.classify_rule(&rule_id) {
+        RuleDisposition::Ignored => "IGNORED",
+        RuleDisposition::Important => "IMPORTANT",
+        RuleDisposition::Normal => "NORMAL",
+    };
+    match env.new_string(disposition) {
+        Ok(output) => output.into_inner(),
+        Err(e) => {
+            throw_exception(
+                &env,
+                &format!("Failed to create a string for the rule disposition:\n{}", e),
+            );
+            JObject::null().into_inner()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configValidate(
+    env: JNIEnv,
+    _class: JClass,
+    config_pointer: jlong,
+) -> jobject {
+    let loaded = &*(config_pointer as *mut LoadedConfig);
+
+    match create_list_of_diagnostics(&env, &loaded.validate()) {
+        Ok(output) => output,
+        Err(e) => {
+            throw_exception(
+                &env,
+                &format!("Failed to create a list of diagnostics:\n{}", e),
+            );
+            JObject::null().into_inner()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `config_pointer` must have been returned by one of the `loadConfig*` functions and not yet
+/// passed to `unloadConfig`.
+#[no_mangle]
+pub unsafe extern "system" fn Java_com_sonatype_configinator_Config_configGetOrigin(
+    env: JNIEnv,
+    _class: JClass,
+    config_pointer: jlong,
+    key: JString,
+) -> jobject {
+    let loaded = &mut *(config_pointer as *mut LoadedConfig);
+
+    let key: String = match env.get_string(key) {
+        Ok(key) => key.into(),
+        Err(e) => {
+            throw_exception(
+                &env,
+                &format!("Could not process the key as a string:\n{}", e),
+            );
+            return JObject::null().into_inner();
+        }
+    };
+    match loaded.origin_of(&key) {
+        Some(origin) => match create_origin(&env, origin) {
+            Ok(output) => output,
+            Err(e) => {
+                throw_exception(
+                    &env,
+                    &format!("Failed to create an origin for {}:\n{}", key, e),
+                );
+                JObject::null().into_inner()
+            }
+        },
+        None => JObject::null().into_inner(),
+    }
+}
+
+fn create_origin(env: &JNIEnv, origin: &Origin) -> Result<jobject, JniError> {
+    // names of the constants in com.sonatype.configinator.Origin.Kind
+    let kind = match origin {
+        Origin::File { .. } => "FILE",
+        Origin::IgnoresFile { .. } => "IGNORES_FILE",
+        Origin::Environment { .. } => "ENVIRONMENT",
+    };
+    let path = match origin.path() {
+        Some(path) => *env.new_string(path.to_string_lossy())?,
+        None => JObject::null(),
+    };
+    // line and column are 0 when the origin has no location
+    let (line, column) = match origin {
+        Origin::File {
+            location: Some(location),
+            ..
+        } => (location.line, location.column),
+        _ => (0, 0),
+    };
+    let variable = match origin {
+        Origin::Environment { variable } => *env.new_string(variable)?,
+        _ => JObject::null(),
+    };
+    let output = env.new_object(
+        ORIGIN_CLASS,
+        ORIGIN_CONSTRUCTOR,
+        &[
+            JValue::Object(*env.new_string(kind)?),
+            JValue::Object(path),
+            JValue::Int(line as i32),
+            JValue::Int(column as i32),
+            JValue::Object(variable),
+        ],
+    )?;
+    Ok(output.into_inner())
+}
+
+fn create_list_of_diagnostics(
+    env: &JNIEnv,
+    diagnostics: &[Diagnostic],
+) -> Result<jobject, JniError> {
+    let output = env.new_object(ARRAYLIST_CLASS, ARRAYLIST_CONSTRUCTOR, &[])?;
+    let list = env.get_list(output)?;
+    for diagnostic in diagnostics {
+        // names of the constants in com.sonatype.configinator.Severity
+        let severity = match diagnostic.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+            Severity::Info => "INFO",
+        };
+        let diagnostic = env.new_object(
+            DIAGNOSTIC_CLASS,
+            DIAGNOSTIC_CONSTRUCTOR,
+            &[
+                JValue::Object(*env.new_string(severity)?),
+                JValue::Object(*env.new_string(diagnostic.code.as_str())?),
+                JValue::Object(*env.new_string(diagnostic.key)?),
+                JValue::Object(*env.new_string(&diagnostic.message)?),
+            ],
+        )?;
+        list.add(diagnostic)?;
+    }
+    Ok(output.into_inner())
+}
+
 fn create_list_of_strings(env: &JNIEnv, values: &Vec<String>) -> Result<jobject, JniError> {
     let output = env.new_object(ARRAYLIST_CLASS, ARRAYLIST_CONSTRUCTOR, &[])?;
     let list = env.get_list(output)?;
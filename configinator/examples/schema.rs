@@ -0,0 +1,6 @@
+fn main() {
+    print!(
+        "{}",
+        configinator::Config::json_schema().expect("Failed to generate the schema")
+    );
+}
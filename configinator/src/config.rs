@@ -1,113 +1,802 @@
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    ffi::OsString,
+    fmt,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
-use serde::{Deserialize, Deserializer};
+use itertools::Itertools;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::diff::{diff, ConfigDiff};
+use crate::env::from_vars;
 use crate::error::ConfigError;
-use crate::filesystem::locate_files;
+use crate::extends::{reject_nested_extends, resolve_extends, with_bases_loaded_by};
+use crate::filesystem::locate_ancestor_files;
+use crate::format::Format;
+use crate::ignore_files::{reroot_patterns, IgnoreMatcher};
+use crate::interpolate::interpolate;
+use crate::keys::KEYS;
+use crate::loaded::{merge_files, with_ignores_file, LoadedConfig};
+use crate::overrides::{for_path, Override};
+use crate::report::{LoadReport, LoadWarning};
+use crate::rules::{classify, RuleDisposition};
+use crate::tools::{resolve, ToolRegistry, ToolSelection};
+use crate::validate::{validate, Diagnostic};
 
 /// A implementation of the configuration detailed on the [Lift configuration reference](https://help.sonatype.com/lift/configuration-reference) page.
-#[derive(Debug, PartialEq, PartialOrd, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "schema",
+    schemars(
+        title = "Lift configuration",
+        description = "Configures how Lift analyzes a repository. See https://help.sonatype.com/lift/configuration-reference"
+    )
+)]
 pub struct Config {
+    /// A shell command that is run before the build, such as one that installs dependencies.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub setup: Option<String>,
 
+    /// The command that builds the project, used instead of the build that Lift detects.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub build: Option<String>,
 
-    #[serde(rename = "importantRules")]
+    /// Rules whose findings are always reported. Entries may use `*` as a wildcard.
+    #[serde(rename = "importantRules", skip_serializing_if = "Option::is_none")]
     pub important_rules: Option<Vec<String>>,
 
-    #[serde(rename = "ignoreRules")]
+    /// Rules whose findings are never reported. Entries may use `*` as a wildcard.
+    #[serde(rename = "ignoreRules", skip_serializing_if = "Option::is_none")]
     pub ignore_rules: Option<Vec<String>>,
 
-    #[serde(rename = "ignoreFiles", deserialize_with = "trim_whitespace", default)]
+    /// Files that are not analyzed, as gitignore-style patterns on separate lines.
+    #[serde(
+        rename = "ignoreFiles",
+        deserialize_with = "deserialize_ignore_files",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "Option<crate::schema::IgnoreFiles>")
+    )]
     pub ignore_files: Option<String>,
 
+    /// The built-in tools to run. When this is not set, every tool that runs by default does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "Option<Vec<crate::schema::ToolName>>")
+    )]
     pub tools: Option<Vec<String>>,
 
-    #[serde(rename = "disableTools")]
+    /// Tools that never run, even if they are listed in `tools` or `customTools`.
+    #[serde(rename = "disableTools", skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "Option<Vec<crate::schema::ToolName>>")
+    )]
     pub disable_tools: Option<Vec<String>>,
 
-    #[serde(rename = "customTools")]
+    /// Additional tools to run alongside the built-in ones.
+    #[serde(rename = "customTools", skip_serializing_if = "Option::is_none")]
     pub custom_tools: Option<Vec<String>>,
 
+    /// Users who are allowed to trigger analysis of the repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub allow: Option<Vec<String>>,
 
-    #[serde(rename = "jdk11")]
+    /// Builds Java projects with JDK 11 rather than the default JDK.
+    #[serde(rename = "jdk11", skip_serializing_if = "Option::is_none")]
     pub jdk_11: Option<bool>,
 
-    #[serde(rename = "androidVersion")]
+    /// The Android API level that Android projects are built against.
+    #[serde(rename = "androidVersion", skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "Option<crate::schema::AndroidVersion>")
+    )]
     pub android_version: Option<u32>,
 
-    #[serde(rename = "errorproneBugPatterns")]
+    /// Error Prone bug patterns to enable in addition to the defaults.
+    #[serde(
+        rename = "errorproneBugPatterns",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub errorprone_bug_patterns: Option<Vec<String>>,
 
-    #[serde(rename = "summaryComments")]
+    /// Posts a summary comment on pull requests.
+    #[serde(rename = "summaryComments", skip_serializing_if = "Option::is_none")]
     pub summary_comments: Option<bool>,
+
+    /// Settings for parts of the repository, each applying to the paths its `paths` patterns
+    /// match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<Vec<Override>>,
+
+    /// Named sets of settings, such as `[profile.nightly]`, that replace the top-level ones when
+    /// selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "Option<BTreeMap<String, crate::schema::NestedConfig>>")
+    )]
+    pub profile: Option<BTreeMap<String, Config>>,
+
+    /// Configuration files that this one is layered over, as a path or a list of paths relative
+    /// to this file. They are loaded first, in order, and this file takes precedence over them.
+    #[serde(
+        deserialize_with = "deserialize_extends",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<crate::schema::Extends>"))]
+    pub extends: Option<Vec<String>>,
 }
 
-fn trim_whitespace<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+/// Reads `extends` as either a single path or a list of them.
+fn deserialize_extends<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let with_whitespace: Option<String> = Option::deserialize(deserializer)?;
+    struct ExtendsVisitor;
 
-    Ok(with_whitespace.map(|with_whitespace| {
-        itertools::join(
-            with_whitespace
-                .lines()
-                .map(str::trim)
-                .filter(|l| !l.is_empty()),
-            "\n",
-        )
-    }))
+    impl<'de> Visitor<'de> for ExtendsVisitor {
+        type Value = Option<Vec<String>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a path or a list of paths")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            Ok(Some(vec![String::from(value)]))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut paths = Vec::new();
+            while let Some(path) = seq.next_element::<String>()? {
+                paths.push(path);
+            }
+            Ok(Some(paths))
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(ExtendsVisitor)
+        }
+    }
+
+    deserializer.deserialize_any(ExtendsVisitor)
+}
+
+/// Reads `ignoreFiles` as a string of patterns on separate lines, trimming each line and dropping
+/// blank ones. A list of patterns, as older Muse configurations used, is read as one pattern per
+/// line.
+fn deserialize_ignore_files<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct IgnoreFilesVisitor;
+
+    impl<'de> Visitor<'de> for IgnoreFilesVisitor {
+        type Value = Option<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string or a list of strings")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            Ok(Some(trim_lines(value)))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut patterns = Vec::new();
+            while let Some(pattern) = seq.next_element::<String>()? {
+                patterns.push(pattern);
+            }
+            Ok(Some(trim_lines(&patterns.join("\n"))))
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(IgnoreFilesVisitor)
+        }
+    }
+
+    deserializer.deserialize_any(IgnoreFilesVisitor)
+}
+
+pub(crate) fn trim_lines(with_whitespace: &str) -> String {
+    itertools::join(
+        with_whitespace
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty()),
+        "\n",
+    )
 }
 
 impl Config {
+    /// Loads a configuration file, parsing it according to its extension (see [`Format::from_path`]).
+    ///
+    /// Files without a recognized extension, such as `.muse/config`, have their format detected
+    /// from their contents.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Config::from_file_with_format(path).map(|(config, _format)| config)
+    }
+
+    /// Loads a configuration file like [`Config::from_file`], also reporting which format it was
+    /// parsed as.
+    pub fn from_file_with_format<P: AsRef<Path>>(path: P) -> Result<(Self, Format), ConfigError> {
         let path = path.as_ref();
-        if path.exists() && path.is_file() {
-            let mut file = File::open(path)?;
-            let mut contents = String::new();
-            file.read_to_string(&mut contents)?;
+        let contents = read_file(path)?;
+        let (config, format) = parse(path, &contents)?;
+        Ok((resolve_extends(path, config)?, format))
+    }
 
-            toml::from_str(&contents).map_err(Into::into)
-        } else {
-            Err(ConfigError::FileNotFound(path.to_path_buf()))
+    /// Loads a configuration file like [`Config::from_file`], but fails on the first key that
+    /// `Config` does not recognize, in the file or in any file it `extends`, instead of ignoring
+    /// it. The keys of each profile and override are checked too.
+    ///
+    /// The error names the unknown key, where it is in the file and the closest known key.
+    /// Deprecated keys are still accepted, as they are migrated to their modern names.
+    pub fn from_file_strict<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let report = Config::from_file_with_warnings(path)?;
+        let unknown_key = report
+            .warnings
+            .into_iter()
+            .find_map(|warning| match warning {
+                LoadWarning::UnknownKey {
+                    key,
+                    location,
+                    suggestion,
+                } => Some((key, location, suggestion)),
+                _ => None,
+            });
+        match unknown_key {
+            Some((key, location, suggestion)) => Err(ConfigError::UnknownKey {
+                key,
+                location,
+                suggestion,
+            }),
+            None => Ok(report.config),
         }
     }
 
+    /// Loads a configuration file like [`Config::from_file`], also collecting warnings about
+    /// unknown or deprecated keys, duplicate list entries and empty `setup` or `build` commands.
+    ///
+    /// The files named in `extends` are checked too, each warning pointing into the file it is
+    /// about. The file itself is reported first, then its bases in the order they are loaded.
+    pub fn from_file_with_warnings<P: AsRef<Path>>(path: P) -> Result<LoadReport, ConfigError> {
+        let path = path.as_ref();
+        let (loaded, format, mut warnings) = LoadedConfig::from_single_file_with_warnings(path)?;
+        let loaded = with_bases_loaded_by(path, loaded, &mut |base: &Path| {
+            let (base, _format, base_warnings) =
+                LoadedConfig::from_single_file_with_warnings(base)?;
+            warnings.extend(base_warnings);
+            Ok(base)
+        })?;
+        Ok(LoadReport {
+            config: loaded.config,
+            format,
+            warnings,
+        })
+    }
+
+    /// Serializes the configuration as TOML, omitting any fields that are not set.
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        Format::Toml.to_string(self)
+    }
+
+    /// Serializes the configuration as pretty-printed JSON, omitting any fields that are not set.
+    pub fn to_json_string(&self) -> Result<String, ConfigError> {
+        Format::Json.to_string(self)
+    }
+
+    /// Serializes the configuration as YAML, omitting any fields that are not set.
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml_string(&self) -> Result<String, ConfigError> {
+        Format::Yaml.to_string(self)
+    }
+
+    /// Generates a JSON Schema describing the configuration files that [`Config`] accepts, for
+    /// editors to offer completion and validation with.
+    ///
+    /// The schema is derived from the same definition that files are deserialized with, so it
+    /// always matches. Unknown keys are allowed, as they are when loading.
+    #[cfg(feature = "schema")]
+    pub fn json_schema() -> Result<String, ConfigError> {
+        Format::Json.to_string(&schemars::schema_for!(Config))
+    }
+
+    /// Writes the configuration to a file in the format matching its extension, defaulting to TOML.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let contents = Format::from_path(path)
+            .unwrap_or(Format::Toml)
+            .to_string(self)?;
+        std::fs::write(path, contents).map_err(Into::into)
+    }
+
+    /// Loads the highest precedence configuration file in the folder.
+    ///
+    /// If the folder has a `.muse/ignoreFiles` file, its lines are appended to `ignoreFiles` after
+    /// any inline entries, as in [`Config::merge`]. A folder with only that file still produces a
+    /// configuration.
     pub fn from_folder<P: AsRef<Path>>(path: P) -> Result<Option<Self>, ConfigError> {
-        let config_files = locate_files(&path)?;
-        if let Some(config_file) = config_files.into_iter().next() {
-            Ok(Some(Config::from_file(config_file)?))
-        } else {
-            Ok(None)
+        Ok(LoadedConfig::from_folder(path)?.map(|loaded| loaded.config))
+    }
+
+    /// Loads every configuration file in the folder and layers them with [`Config::merge`].
+    ///
+    /// Files are applied from the lowest precedence to the highest, so earlier entries in the
+    /// discovery list (e.g. `.lift/config.toml`) win over later ones (e.g. `.muse.toml`).
+    pub fn from_folder_merged<P: AsRef<Path>>(path: P) -> Result<Option<Self>, ConfigError> {
+        Ok(LoadedConfig::from_folder_merged(path)?.map(|loaded| loaded.config))
+    }
+
+    /// Searches `path` and then each of its ancestors, returning the nearest configuration.
+    ///
+    /// The search stops at the repository root (a folder containing `.git`), at `ceiling` (which
+    /// is still searched), or at a filesystem boundary. Folders with only a `.muse/ignoreFiles`
+    /// file are passed over. The configuration files within the folder that is found are combined
+    /// as in [`Config::from_folder_merged`], along with that folder's `.muse/ignoreFiles` file.
+    pub fn from_ancestors<P: AsRef<Path>>(
+        path: P,
+        ceiling: Option<&Path>,
+    ) -> Result<Option<AncestorConfig>, ConfigError> {
+        let nearest = locate_ancestor_files(path, ceiling)?
+            .into_iter()
+            .find(|(_directory, config_files)| !config_files.is_empty());
+        match nearest {
+            Some((directory, config_files)) => {
+                let loaded = with_ignores_file(&directory, merge_files(config_files)?)?;
+                Ok(loaded.map(|loaded| AncestorConfig {
+                    config: loaded.config,
+                    directories: vec![directory],
+                }))
+            }
+            None => Ok(None),
         }
     }
-}
 
-impl Default for Config {
-    fn default() -> Self {
+    /// Searches like [`Config::from_ancestors`], but layers every folder that has a configuration,
+    /// so that nearer configurations are merged over the outer ones with [`Config::merge`].
+    ///
+    /// The patterns in `ignoreFiles` and in the `paths` of `[[overrides]]` are rewritten to be
+    /// relative to the outermost folder, the last of `directories`, so paths checked against the
+    /// layered configuration must be relative to that folder too.
+    pub fn from_ancestors_layered<P: AsRef<Path>>(
+        path: P,
+        ceiling: Option<&Path>,
+    ) -> Result<Option<AncestorConfig>, ConfigError> {
+        let found = locate_ancestor_files(path, ceiling)?;
+        let mut layered: Option<AncestorConfig> = None;
+        for (directory, config_files) in found.into_iter().rev() {
+            let loaded = merge_files(config_files)?;
+            if let Some(LoadedConfig { config, .. }) = with_ignores_file(&directory, loaded)? {
+                layered = Some(match layered {
+                    Some(outer) => AncestorConfig {
+                        config: outer.config.merge(config.rerooted(relative_to(
+                            &directory,
+                            outer.directories.last().expect("layered from a folder"),
+                        ))),
+                        directories: std::iter::once(directory)
+                            .chain(outer.directories)
+                            .collect(),
+                    },
+                    None => AncestorConfig {
+                        config,
+                        directories: vec![directory],
+                    },
+                });
+            }
+        }
+        Ok(layered)
+    }
+
+    /// Rewrites the patterns in `ignoreFiles` and in the `paths` of `[[overrides]]`, which are
+    /// written relative to `folder`, to be relative to the root that `folder` is in. Profiles are
+    /// rewritten the same way.
+    pub(crate) fn rerooted(self, folder: &Path) -> Config {
+        Config {
+            ignore_files: self
+                .ignore_files
+                .map(|patterns| reroot_patterns(&patterns, folder)),
+            overrides: self.overrides.map(|overrides| {
+                overrides
+                    .into_iter()
+                    .map(|entry| Override {
+                        paths: entry
+                            .paths
+                            .iter()
+                            .map(|pattern| reroot_patterns(pattern, folder))
+                            .collect(),
+                        config: entry.config.rerooted(folder),
+                    })
+                    .collect()
+            }),
+            profile: self.profile.map(|profiles| {
+                profiles
+                    .into_iter()
+                    .map(|(name, profile)| (name, profile.rerooted(folder)))
+                    .collect()
+            }),
+            ..self
+        }
+    }
+
+    /// Compiles `ignoreFiles` into a matcher, failing if any of its patterns are invalid.
+    ///
+    /// See [`IgnoreMatcher`] for how the patterns are resolved relative to the repository root.
+    pub fn ignore_matcher(&self) -> Result<IgnoreMatcher, ConfigError> {
+        IgnoreMatcher::new(self.ignore_files.as_deref().unwrap_or_default())
+    }
+
+    /// Checks whether a file, given relative to the repository root, is excluded by `ignoreFiles`.
+    ///
+    /// Invalid patterns are skipped, as git does. This compiles the patterns on every call, so
+    /// prefer [`Config::ignore_matcher`] when checking many files.
+    pub fn is_file_ignored<P: AsRef<Path>>(&self, relative_path: P) -> bool {
+        IgnoreMatcher::new_lenient(self.ignore_files.as_deref().unwrap_or_default())
+            .is_ignored(relative_path)
+    }
+
+    /// The effective settings for a file or directory, given relative to the repository root.
+    ///
+    /// Every `[[overrides]]` table whose `paths` match the path is layered on top of the
+    /// top-level settings with [`Config::merge`], in the order they are written, so later tables
+    /// take precedence. The result has no `overrides` of its own.
+    pub fn for_path<P: AsRef<Path>>(&self, relative_path: P) -> Config {
+        for_path(self, relative_path.as_ref())
+    }
+
+    /// Selects a profile, whose fields replace the top-level ones with [`Config::overridden_by`].
+    ///
+    /// The result has no profiles of its own, so profiles cannot select other profiles. Fails
+    /// with [`ConfigError::UnknownProfile`], listing the profiles that exist, if there is no
+    /// `[profile.<name>]` table.
+    pub fn with_profile(mut self, name: &str) -> Result<Config, ConfigError> {
+        let mut profiles = self.profile.take().unwrap_or_default();
+        match profiles.remove(name) {
+            Some(profile) => Ok(self.overridden_by(Config {
+                profile: None,
+                ..profile
+            })),
+            None => Err(ConfigError::UnknownProfile {
+                name: String::from(name),
+                available: profiles.into_keys().collect(),
+            }),
+        }
+    }
+
+    /// Decides whether findings for a rule are ignored, important or reported normally.
+    ///
+    /// `importantRules` and `ignoreRules` entries may contain `*` wildcards. A rule matching both
+    /// lists takes the disposition of the more specific entry, and is ignored on a tie; see
+    /// [`RuleDisposition`].
+    pub fn classify_rule(&self, rule_id: &str) -> RuleDisposition {
+        classify(
+            self.important_rules.as_deref().unwrap_or_default(),
+            self.ignore_rules.as_deref().unwrap_or_default(),
+            rule_id,
+        )
+    }
+
+    /// Works out which tools run from `tools`, `disableTools` and `customTools`.
+    ///
+    /// Every built-in tool in the registry is reported, along with the custom tools and any
+    /// unknown names, each marked as enabled or not and with any conflicts found. See
+    /// [`ToolSelection`].
+    pub fn effective_tools(&self, registry: &ToolRegistry) -> Vec<ToolSelection> {
+        resolve(
+            registry,
+            self.tools.as_deref(),
+            self.disable_tools.as_deref().unwrap_or_default(),
+            self.custom_tools.as_deref().unwrap_or_default(),
+        )
+    }
+
+    /// Checks the values of the configuration for settings that can never work or that probably
+    /// do not do what was intended, such as an `androidVersion` that is not an Android API level
+    /// or a rule that is both important and ignored.
+    ///
+    /// Each [`Diagnostic`] has a [`Severity`](crate::Severity); callers should refuse to start an
+    /// analysis if any are errors. The diagnostics have no location; validate a
+    /// [`LoadedConfig`] to point them at the keys in the file.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate(self)
+    }
+
+    /// Lists the list fields that are set, by their key.
+    pub(crate) fn lists(&self) -> Vec<(&'static str, &Vec<String>)> {
+        [
+            ("importantRules", &self.important_rules),
+            ("ignoreRules", &self.ignore_rules),
+            ("tools", &self.tools),
+            ("disableTools", &self.disable_tools),
+            ("customTools", &self.custom_tools),
+            ("allow", &self.allow),
+            ("errorproneBugPatterns", &self.errorprone_bug_patterns),
+        ]
+        .into_iter()
+        .filter_map(|(key, values)| values.as_ref().map(|values| (key, values)))
+        .collect()
+    }
+
+    /// Builds a configuration from the environment variables that start with `prefix`, such as
+    /// `LIFT_BUILD` or `LIFT_TOOLS=infer,eslint` with [`DEFAULT_ENV_PREFIX`](crate::DEFAULT_ENV_PREFIX).
+    ///
+    /// Each key is read from the prefix followed by the key in upper snake case, so
+    /// `importantRules` is read from `LIFT_IMPORTANT_RULES`. Lists are comma-separated, with each
+    /// entry trimmed and empty entries dropped; booleans are `true`, `false`, `1` or `0`. Other
+    /// variables with the prefix are ignored. Apply the result to a loaded configuration with
+    /// [`Config::overridden_by`].
+    pub fn from_env(prefix: &str) -> Result<Self, ConfigError> {
+        from_vars(prefix, std::env::vars_os())
+    }
+
+    /// Builds a configuration from variables like [`Config::from_env`], but takes them from
+    /// `vars` instead of the process environment.
+    pub fn from_vars<I, K, V>(prefix: &str, vars: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        from_vars(
+            prefix,
+            vars.into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        )
+    }
+
+    /// Substitutes variables in `setup` and `build`, looking each one up in `vars` and then in the
+    /// environment.
+    ///
+    /// `${NAME}` is replaced with the variable's value, and `${NAME:-default}` falls back to
+    /// `default` when it is undefined or empty; defaults cannot contain variables of their own.
+    /// `$$` is a literal `$`, so `$${NAME}` is left for the shell, as is any `$` that is not
+    /// followed by `{`. An undefined variable without a default is an error rather than being
+    /// replaced with nothing.
+    ///
+    /// Interpolation is opt-in: loading a configuration never substitutes anything.
+    pub fn interpolate(self, vars: &HashMap<String, String>) -> Result<Self, ConfigError> {
+        self.interpolate_with(|name| vars.get(name).cloned().or_else(|| env::var(name).ok()))
+    }
+
+    /// Substitutes variables like [`Config::interpolate`], looking each one up with `lookup` only.
+    pub fn interpolate_with<F>(self, lookup: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Ok(Config {
+            setup: self
+                .setup
+                .map(|setup| interpolate("setup", &setup, &lookup))
+                .transpose()?,
+            build: self
+                .build
+                .map(|build| interpolate("build", &build, &lookup))
+                .transpose()?,
+            ..self
+        })
+    }
+
+    /// Replaces every field of `self` that is set in `overrides`.
+    ///
+    /// Unlike [`Config::merge`], lists are replaced rather than concatenated, so that an
+    /// override such as `LIFT_IMPORTANT_RULES` says exactly what the value is.
+    pub fn overridden_by(self, overrides: Config) -> Config {
         Config {
-            setup: None,
-            build: None,
-            important_rules: None,
-            ignore_rules: None,
-            ignore_files: None,
-            tools: None,
-            disable_tools: None,
-            custom_tools: None,
-            allow: None,
-            jdk_11: None,
-            android_version: None,
-            errorprone_bug_patterns: None,
-            summary_comments: None,
+            setup: overrides.setup.or(self.setup),
+            build: overrides.build.or(self.build),
+            important_rules: overrides.important_rules.or(self.important_rules),
+            ignore_rules: overrides.ignore_rules.or(self.ignore_rules),
+            ignore_files: overrides.ignore_files.or(self.ignore_files),
+            tools: overrides.tools.or(self.tools),
+            disable_tools: overrides.disable_tools.or(self.disable_tools),
+            custom_tools: overrides.custom_tools.or(self.custom_tools),
+            allow: overrides.allow.or(self.allow),
+            jdk_11: overrides.jdk_11.or(self.jdk_11),
+            android_version: overrides.android_version.or(self.android_version),
+            errorprone_bug_patterns: overrides
+                .errorprone_bug_patterns
+                .or(self.errorprone_bug_patterns),
+            summary_comments: overrides.summary_comments.or(self.summary_comments),
+            overrides: overrides.overrides.or(self.overrides),
+            profile: overrides.profile.or(self.profile),
+            extends: overrides.extends.or(self.extends),
         }
     }
+
+    /// Compares `self` with `other`, treating `self` as the old configuration.
+    ///
+    /// Lists of strings that are set in both are compared as sets, reporting the entries that were
+    /// added and removed, as are the lines of `ignoreFiles`; every other change, including a list
+    /// being set or unset or a change to `overrides`, reports the old and new values. See
+    /// [`ConfigDiff`] for how the result is displayed and serialized.
+    pub fn diff(&self, other: &Config) -> ConfigDiff {
+        diff(self, other)
+    }
+
+    /// The keys that are set, as written in configuration files.
+    pub(crate) fn set_keys(&self) -> Vec<&'static str> {
+        let value = serde_json::to_value(self).expect("a Config always serializes to JSON");
+        KEYS.iter()
+            .copied()
+            .filter(|key| value.get(key).is_some())
+            .collect()
+    }
+
+    /// Layers `other` on top of `self`, with `other` taking precedence.
+    ///
+    /// Each field is combined according to its kind:
+    ///
+    /// | Field | Rule |
+    /// |-------|------|
+    /// | `setup`, `build`, `jdk11`, `androidVersion`, `summaryComments` | overridden by `other` when set |
+    /// | `tools`, `disableTools`, `customTools`, `allow` | replaced by `other` when set |
+    /// | `importantRules`, `ignoreRules`, `errorproneBugPatterns` | concatenated, `self` first, duplicates dropped |
+    /// | `ignoreFiles` | concatenated line by line, `self` first |
+    /// | `overrides` | concatenated, `self` first |
+    /// | `profile` | profiles with the same name merged by these rules |
+    /// | `extends` | overridden by `other` when set |
+    ///
+    /// Tool selection and `allow` are replaced rather than concatenated because they restrict what
+    /// runs, so a higher precedence file must be able to narrow them. `ignoreFiles` keeps duplicate
+    /// lines because the order of gitignore-style patterns is significant.
+    pub fn merge(self, other: Config) -> Config {
+        Config {
+            setup: other.setup.or(self.setup),
+            build: other.build.or(self.build),
+            important_rules: concat_lists(self.important_rules, other.important_rules),
+            ignore_rules: concat_lists(self.ignore_rules, other.ignore_rules),
+            ignore_files: concat_lines(self.ignore_files, other.ignore_files),
+            tools: other.tools.or(self.tools),
+            disable_tools: other.disable_tools.or(self.disable_tools),
+            custom_tools: other.custom_tools.or(self.custom_tools),
+            allow: other.allow.or(self.allow),
+            jdk_11: other.jdk_11.or(self.jdk_11),
+            android_version: other.android_version.or(self.android_version),
+            errorprone_bug_patterns: concat_lists(
+                self.errorprone_bug_patterns,
+                other.errorprone_bug_patterns,
+            ),
+            summary_comments: other.summary_comments.or(self.summary_comments),
+            overrides: concat_overrides(self.overrides, other.overrides),
+            profile: merge_profiles(self.profile, other.profile),
+            extends: other.extends.or(self.extends),
+        }
+    }
+}
+
+pub(crate) fn read_file(path: &Path) -> Result<String, ConfigError> {
+    if path.exists() && path.is_file() {
+        let mut file = File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        Ok(contents)
+    } else {
+        Err(ConfigError::FileNotFound(path.to_path_buf()))
+    }
+}
+
+/// Parses the contents according to the file extension, detecting the format if there isn't one.
+///
+/// Fails if `extends` is set anywhere but the top level, as it is only resolved there.
+pub(crate) fn parse(path: &Path, contents: &str) -> Result<(Config, Format), ConfigError> {
+    let format = match Format::from_path(path) {
+        Some(format) => format,
+        None => Format::detect::<Config>(path, contents)?.0,
+    };
+    let config = format.parse_config(path, contents)?;
+    reject_nested_extends(path, &config)?;
+    Ok((config, format))
+}
+
+/// The path of `folder` relative to `root`, which is one of its ancestors.
+fn relative_to<'a>(folder: &'a Path, root: &Path) -> &'a Path {
+    folder.strip_prefix(root).unwrap_or(folder)
+}
+
+/// A configuration found by searching a folder and its ancestors.
+#[derive(Debug, PartialEq)]
+pub struct AncestorConfig {
+    pub config: Config,
+
+    /// The folders the configuration was loaded from, nearest first.
+    pub directories: Vec<PathBuf>,
+}
+
+/// The keys whose values [`Config::merge`] concatenates rather than replaces.
+pub(crate) const CONCATENATED_KEYS: &[&str] = &[
+    "importantRules",
+    "ignoreRules",
+    "errorproneBugPatterns",
+    "ignoreFiles",
+    "overrides",
+    "profile",
+];
+
+fn concat_lists(base: Option<Vec<String>>, other: Option<Vec<String>>) -> Option<Vec<String>> {
+    match (base, other) {
+        (Some(base), Some(other)) => Some(base.into_iter().chain(other).unique().collect()),
+        (base, other) => other.or(base),
+    }
+}
+
+fn merge_profiles(
+    base: Option<BTreeMap<String, Config>>,
+    other: Option<BTreeMap<String, Config>>,
+) -> Option<BTreeMap<String, Config>> {
+    match (base, other) {
+        (Some(mut base), Some(other)) => {
+            for (name, profile) in other {
+                let merged = match base.remove(&name) {
+                    Some(existing) => existing.merge(profile),
+                    None => profile,
+                };
+                base.insert(name, merged);
+            }
+            Some(base)
+        }
+        (base, other) => other.or(base),
+    }
+}
+
+fn concat_overrides(
+    base: Option<Vec<Override>>,
+    other: Option<Vec<Override>>,
+) -> Option<Vec<Override>> {
+    match (base, other) {
+        (Some(base), Some(other)) => Some(base.into_iter().chain(other).collect()),
+        (base, other) => other.or(base),
+    }
+}
+
+fn concat_lines(base: Option<String>, other: Option<String>) -> Option<String> {
+    match (base, other) {
+        (Some(base), Some(other)) if base.is_empty() => Some(other),
+        (Some(base), Some(other)) if other.is_empty() => Some(base),
+        (Some(base), Some(other)) => Some(format!("{}\n{}", base, other)),
+        (base, other) => other.or(base),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::env::DEFAULT_ENV_PREFIX;
+    use crate::keys::KEYS;
+    use temp_testdir::TempDir;
 
     #[test]
     fn it_should_parse_the_documentation_example() {
@@ -183,14 +872,458 @@ src/test/"#,
     }
 
     #[test]
-    fn it_should_fail_to_parse_a_file_that_is_not_toml() {
-        let error = Config::from_file("examples/.lift.json").expect_err("This should have failed");
-        if let ConfigError::FileTomlParseFailed(_e) = error {
+    fn it_should_parse_a_json_file() {
+        let expected = Config {
+            setup: Some(String::from("echo 'Hello, Lift'")),
+            allow: Some(vec![String::from("amy")]),
+            summary_comments: Some(true),
+            ..Default::default()
+        };
+        let actual =
+            Config::from_file("examples/.lift.json").expect("Failed to parse example json");
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn it_should_parse_a_yaml_file() {
+        let expected = Config {
+            setup: Some(String::from("echo 'Hello, Lift'")),
+            allow: Some(vec![String::from("amy")]),
+            summary_comments: Some(true),
+            ..Default::default()
+        };
+        let actual =
+            Config::from_file("examples/.lift.yaml").expect("Failed to parse example yaml");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn it_should_detect_json_in_an_extensionless_file() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".muse")).expect("Failed to create .muse directory");
+        std::fs::write(temp.join(".muse/config"), r#"{"build": "make"}"#)
+            .expect("Failed to write .muse/config");
+
+        let expected = Config {
+            build: Some(String::from("make")),
+            ..Default::default()
+        };
+        let actual = Config::from_file_with_format(temp.join(".muse/config"))
+            .expect("Failed to detect the format");
+        assert_eq!(actual, (expected, Format::Json));
+    }
+
+    #[test]
+    fn it_should_know_every_key_that_is_serialized() {
+        let serialized: serde_json::Map<String, serde_json::Value> = serde_json::from_str(
+            &Config {
+                extends: Some(vec![String::from("base.toml")]),
+                ..every_field()
+            }
+            .to_json_string()
+            .expect("Failed to serialize json"),
+        )
+        .expect("Failed to parse json");
+        let mut keys: Vec<&str> = serialized.keys().map(String::as_str).collect();
+        let mut known = KEYS.to_vec();
+        keys.sort_unstable();
+        known.sort_unstable();
+        assert_eq!(keys, known);
+    }
+
+    #[test]
+    fn it_should_accept_known_keys_when_strict() {
+        Config::from_file_strict("examples/.lift.toml").expect("Failed to parse example toml");
+        Config::from_file_strict("examples/.lift.json").expect("Failed to parse example json");
+    }
+
+    #[test]
+    fn it_should_reject_unknown_keys_when_strict() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "build = \"make\"\n\nimportantRule = [\"NULL_DEREFERENCE\"]\n",
+        )
+        .expect("Failed to write .lift.toml");
+
+        let error = Config::from_file_strict(&path).expect_err("This should have failed");
+        match &error {
+            ConfigError::UnknownKey {
+                key,
+                location: Some(location),
+                suggestion,
+            } => {
+                assert_eq!(key, "importantRule");
+                assert_eq!((location.line, location.column), (3, 1));
+                assert_eq!(suggestion.as_deref(), Some("importantRules"));
+            }
+            e => panic!("Expected an UnknownKey with a location, but got {:?}", e),
+        }
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Unknown key `importantRule` at {}:3:1, did you mean `importantRules`?",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_locate_unknown_keys_in_json_when_strict() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.json");
+        std::fs::write(
+            &path,
+            "{\n  \"build\": \"make\",\n  \"ignorefiles\": \"build/\"\n}",
+        )
+        .expect("Failed to write .lift.json");
+
+        let error = Config::from_file_strict(&path).expect_err("This should have failed");
+        match error {
+            ConfigError::UnknownKey {
+                key,
+                location: Some(location),
+                suggestion,
+            } => {
+                assert_eq!(key, "ignorefiles");
+                assert_eq!((location.line, location.column), (3, 3));
+                assert_eq!(suggestion.as_deref(), Some("ignoreFiles"));
+            }
+            e => panic!("Expected an UnknownKey with a location, but got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn it_should_reject_unknown_keys_in_profiles_and_overrides_when_strict() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "build = \"make\"\n\n[profile.nightly]\nimportantRule = [\"A\"]\n",
+        )
+        .expect("Failed to write .lift.toml");
+
+        let error = Config::from_file_strict(&path).expect_err("This should have failed");
+        match error {
+            ConfigError::UnknownKey {
+                key,
+                location: Some(location),
+                suggestion,
+            } => {
+                assert_eq!(key, "importantRule");
+                assert_eq!((location.line, location.column), (4, 1));
+                assert_eq!(suggestion.as_deref(), Some("importantRules"));
+            }
+            e => panic!("Expected an UnknownKey with a location, but got {:?}", e),
+        }
+
+        let json = temp.join(".lift.json");
+        std::fs::write(
+            &json,
+            "{\n  \"overrides\": [\n    {\"paths\": [\"web/\"]},\n    {\"paths\": [\"api/\"], \"toolz\": [\"infer\"]}\n  ]\n}",
+        )
+        .expect("Failed to write .lift.json");
+
+        let error = Config::from_file_strict(&json).expect_err("This should have failed");
+        match error {
+            ConfigError::UnknownKey {
+                key,
+                location: Some(location),
+                suggestion,
+            } => {
+                assert_eq!(key, "toolz");
+                assert_eq!((location.line, location.column), (4, 25));
+                assert_eq!(suggestion.as_deref(), Some("tools"));
+            }
+            e => panic!("Expected an UnknownKey with a location, but got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn it_should_migrate_legacy_keys() {
+        let temp = TempDir::default();
+        let legacy = temp.join(".lift.toml");
+        let modern = temp.join(".lift/config.toml");
+        std::fs::create_dir_all(temp.join(".lift")).expect("Failed to create .lift directory");
+        std::fs::write(
+            &legacy,
+            r#"important_rules = ["A"]
+ignore_rules = ["B"]
+ignore_files = ["build/", " src/test/ "]
+disable_tools = ["infer"]
+custom_tools = ["mytool"]
+jdk_11 = true
+android_version = 30
+errorprone_bug_patterns = ["C"]
+summary_comments = false
+"#,
+        )
+        .expect("Failed to write .lift.toml");
+        std::fs::write(
+            &modern,
+            r#"importantRules = ["A"]
+ignoreRules = ["B"]
+ignoreFiles = """
+build/
+src/test/
+"""
+disableTools = ["infer"]
+customTools = ["mytool"]
+jdk11 = true
+androidVersion = 30
+errorproneBugPatterns = ["C"]
+summaryComments = false
+"#,
+        )
+        .expect("Failed to write .lift/config.toml");
+
+        let report = Config::from_file_with_warnings(&legacy).expect("Failed to parse file");
+        assert_eq!(
+            report.config,
+            Config::from_file(&modern).expect("Failed to parse file")
+        );
+        assert_eq!(
+            report.config.ignore_files.as_deref(),
+            Some("build/\nsrc/test/")
+        );
+        let warnings: Vec<String> = report.warnings.iter().map(LoadWarning::message).collect();
+        assert_eq!(warnings.len(), 10);
+        assert_eq!(
+            warnings[0],
+            "Deprecated key `important_rules` was migrated, use `importantRules` instead"
+        );
+        assert!(warnings.contains(&String::from(
+            "Deprecated shape of `ignoreFiles` was migrated, use a string with one pattern per line instead"
+        )));
+    }
+
+    #[test]
+    fn it_should_accept_deprecated_keys_when_strict() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "summary_comments = true\n").expect("Failed to write .lift.toml");
+
+        let config = Config::from_file_strict(&path).expect("Failed to parse file");
+        assert_eq!(config.summary_comments, Some(true));
+    }
+
+    #[test]
+    fn it_should_prefer_modern_keys_over_deprecated_ones() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            r#"importantRules = ["A"]
+important_rules = ["B"]
+jdk_11 = true
+
+[profile.nightly]
+summary_comments = false
+summaryComments = true
+"#,
+        )
+        .expect("Failed to write .lift.toml");
+
+        let report = Config::from_file_with_warnings(&path).expect("Failed to parse file");
+        assert_eq!(report.config.important_rules, Some(vec![String::from("A")]));
+        assert_eq!(report.config.jdk_11, Some(true));
+        let nightly = report
+            .config
+            .with_profile("nightly")
+            .expect("Failed to select profile");
+        assert_eq!(nightly.summary_comments, Some(true));
+        let warnings: Vec<String> = report.warnings.iter().map(LoadWarning::message).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Deprecated key `important_rules` is ignored, as `importantRules` is also set",
+                "Deprecated key `jdk_11` was migrated, use `jdk11` instead",
+                "Deprecated key `summary_comments` is ignored, as `summaryComments` is also set"
+            ]
+        );
+
+        let json = temp.join(".lift.json");
+        std::fs::write(&json, r#"{"ignore_rules": ["B"], "ignoreRules": "A"}"#)
+            .expect("Failed to write .lift.json");
+        let error = Config::from_file(&json).expect_err("This should have failed");
+        assert!(matches!(error, ConfigError::FileJsonParseFailed { .. }));
+    }
+
+    #[test]
+    fn it_should_locate_invalid_values_next_to_legacy_keys() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "jdk_11 = true\nandroidVersion = \"x\"\n")
+            .expect("Failed to write .lift.toml");
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        assert!(matches!(error, ConfigError::FileTomlParseFailed { .. }));
+        let location = error.location().expect("Expected a location");
+        assert_eq!((location.line, location.column), (2, 18));
+        assert_eq!(&*location.line_text, "androidVersion = \"x\"");
+
+        let json = temp.join(".lift.json");
+        std::fs::write(
+            &json,
+            "{\n  \"jdk_11\": true,\n  \"androidVersion\": \"x\"\n}",
+        )
+        .expect("Failed to write .lift.json");
+        let error = Config::from_file(&json).expect_err("This should have failed");
+        assert!(matches!(error, ConfigError::FileJsonParseFailed { .. }));
+        let location = error.location().expect("Expected a location");
+        assert_eq!(location.line, 3);
+        assert_eq!(&*location.line_text, "  \"androidVersion\": \"x\"");
+    }
+
+    #[test]
+    fn it_should_collect_warnings() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            r#"build = "  "
+important_rules = ["NULL_DEREFERENCE"]
+tools = ["infer", "eslint", "infer"]
+alow = ["amy"]
+"#,
+        )
+        .expect("Failed to write .lift.toml");
+
+        let report = Config::from_file_with_warnings(&path).expect("Failed to parse file");
+        let expected = Config {
+            build: Some(String::from("  ")),
+            important_rules: Some(vec![String::from("NULL_DEREFERENCE")]),
+            tools: Some(vec![
+                String::from("infer"),
+                String::from("eslint"),
+                String::from("infer"),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(report.config, expected);
+        let warnings: Vec<String> = report
+            .warnings
+            .iter()
+            .map(|warning| {
+                warning
+                    .to_string()
+                    .replace(&path.display().to_string(), ".lift.toml")
+            })
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                ".lift.toml:2:1: Deprecated key `important_rules` was migrated, use `importantRules` instead",
+                ".lift.toml:4:1: Unknown key `alow` is ignored, did you mean `allow`?",
+                ".lift.toml:3:1: `tools` lists `infer` more than once",
+                ".lift.toml:1:1: `build` is empty",
+            ]
+        );
+        assert_eq!(
+            report.warnings[0].render(),
+            format!(
+                "warning: {}: Deprecated key `important_rules` was migrated, use `importantRules` instead\n  \
+                 |\n2 | important_rules = [\"NULL_DEREFERENCE\"]\n  | ^^^^^^^^^^^^^^^",
+                report.warnings[0].location().expect("Expected a location")
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_collect_warnings_in_profiles_and_overrides() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            r#"build = "make"
+
+[profile.nightly]
+importantRule = ["A"]
+summary_comments = false
+
+[[overrides]]
+paths = ["web/"]
+toolz = ["eslint"]
+tools = ["eslint", "eslint"]
+"#,
+        )
+        .expect("Failed to write .lift.toml");
+
+        let report = Config::from_file_with_warnings(&path).expect("Failed to parse file");
+        let warnings: Vec<String> = report
+            .warnings
+            .iter()
+            .map(|warning| {
+                warning
+                    .to_string()
+                    .replace(&path.display().to_string(), ".lift.toml")
+            })
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                ".lift.toml:4:1: Unknown key `importantRule` is ignored, did you mean `importantRules`?",
+                ".lift.toml:5:1: Deprecated key `summary_comments` was migrated, use `summaryComments` instead",
+                ".lift.toml:9:1: Unknown key `toolz` is ignored, did you mean `tools`?",
+                ".lift.toml:10:1: `tools` lists `eslint` more than once",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_fail_to_parse_a_toml_file_that_is_not_toml() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "{\"setup\": \"make\"}").expect("Failed to write .lift.toml");
+
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        if let ConfigError::FileTomlParseFailed { location, .. } = &error {
+            let location = location.as_ref().expect("Expected a location");
+            assert_eq!((location.line, location.column), (1, 1));
         } else {
             panic!("Expected a FileTomlParseFailed, but got {:?}", error);
         }
     }
 
+    #[test]
+    fn it_should_fail_to_parse_a_json_file_that_is_not_json() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.json");
+        std::fs::write(&path, "setup = \"make\"").expect("Failed to write .lift.json");
+
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        if let ConfigError::FileJsonParseFailed { location, .. } = &error {
+            let location = location.as_ref().expect("Expected a location");
+            assert_eq!((location.line, location.column), (1, 1));
+        } else {
+            panic!("Expected a FileJsonParseFailed, but got {:?}", error);
+        }
+    }
+
+    #[test]
+    fn it_should_render_parse_errors_with_a_snippet() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "setup = \"make\"\n\ntools = [\"infer\" \"eslint\"]\n",
+        )
+        .expect("Failed to write .lift.toml");
+
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        let location = error.location().expect("Expected a location");
+        assert_eq!((location.line, location.column), (3, 18));
+        std::fs::remove_file(&path).expect("Failed to remove .lift.toml");
+        assert_eq!(
+            error.render(),
+            format!(
+                "error: {}\n  |\n3 | tools = [\"infer\" \"eslint\"]\n  |                  ^",
+                error
+            )
+        );
+    }
+
     #[test]
     fn it_should_parse_a_folder() {
         let expected = Config {
@@ -203,6 +1336,549 @@ src/test/"#,
         assert_eq!(actual, Some(expected));
     }
 
+    fn every_field() -> Config {
+        Config {
+            setup: Some(String::from("echo 'Hello, Lift'")),
+            build: Some(String::from("make")),
+            important_rules: Some(vec![String::from("rule A")]),
+            ignore_rules: Some(vec![String::from("rule 2")]),
+            ignore_files: Some(String::from("build/\n**/requirements.txt")),
+            tools: Some(vec![String::from("infer")]),
+            disable_tools: Some(vec![String::from("eslint")]),
+            custom_tools: Some(vec![String::from("custom")]),
+            allow: Some(vec![String::from("amy")]),
+            jdk_11: Some(true),
+            android_version: Some(28),
+            errorprone_bug_patterns: Some(vec![String::from("bug pattern")]),
+            summary_comments: Some(false),
+            overrides: Some(vec![Override {
+                paths: vec![String::from("android/")],
+                config: Config {
+                    android_version: Some(30),
+                    ..Default::default()
+                },
+            }]),
+            profile: Some(BTreeMap::from([(
+                String::from("nightly"),
+                Config {
+                    tools: Some(vec![String::from("infer"), String::from("errorprone")]),
+                    ..Default::default()
+                },
+            )])),
+            // Loading a file resolves `extends` and leaves it unset, so it cannot round trip.
+            extends: None,
+        }
+    }
+
+    const PROFILES: &str = r#"
+build = "make"
+tools = ["eslint"]
+importantRules = ["A"]
+
+[profile.nightly]
+tools = ["infer", "eslint", "errorprone"]
+importantRules = ["A", "B"]
+
+[profile.pr]
+summaryComments = true
+"#;
+
+    #[test]
+    fn it_should_select_a_profile() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, PROFILES).expect("Failed to write .lift.toml");
+        let config = Config::from_file(&path).expect("Failed to parse file");
+
+        let nightly = config
+            .clone()
+            .with_profile("nightly")
+            .expect("Failed to select the profile");
+        let expected = Config {
+            build: Some(String::from("make")),
+            tools: Some(vec![
+                String::from("infer"),
+                String::from("eslint"),
+                String::from("errorprone"),
+            ]),
+            important_rules: Some(vec![String::from("A"), String::from("B")]),
+            ..Default::default()
+        };
+        assert_eq!(nightly, expected);
+
+        let pr = config
+            .with_profile("pr")
+            .expect("Failed to select the profile");
+        assert_eq!(pr.tools, Some(vec![String::from("eslint")]));
+        assert_eq!(pr.summary_comments, Some(true));
+        assert_eq!(pr.profile, None);
+    }
+
+    #[test]
+    fn it_should_list_the_profiles_when_one_is_unknown() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, PROFILES).expect("Failed to write .lift.toml");
+        let config = Config::from_file(&path).expect("Failed to parse file");
+
+        let error = config
+            .with_profile("weekly")
+            .expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            "Unknown profile `weekly`, the available profiles are `nightly`, `pr`"
+        );
+        let error = Config::default()
+            .with_profile("weekly")
+            .expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            "Unknown profile `weekly`, no profiles are defined"
+        );
+    }
+
+    #[test]
+    fn it_should_merge_profiles_with_the_same_name() {
+        let low = Config {
+            profile: Some(BTreeMap::from([(
+                String::from("nightly"),
+                Config {
+                    important_rules: Some(vec![String::from("A")]),
+                    jdk_11: Some(false),
+                    ..Default::default()
+                },
+            )])),
+            ..Default::default()
+        };
+        let high = Config {
+            profile: Some(BTreeMap::from([
+                (
+                    String::from("nightly"),
+                    Config {
+                        important_rules: Some(vec![String::from("B")]),
+                        ..Default::default()
+                    },
+                ),
+                (String::from("pr"), Config::default()),
+            ])),
+            ..Default::default()
+        };
+        let merged = low.merge(high);
+        let profiles = merged.profile.expect("Expected profiles");
+        assert_eq!(profiles.keys().collect::<Vec<_>>(), vec!["nightly", "pr"]);
+        assert_eq!(
+            profiles["nightly"],
+            Config {
+                important_rules: Some(vec![String::from("A"), String::from("B")]),
+                jdk_11: Some(false),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_serialize_only_the_set_fields() {
+        let config = Config {
+            build: Some(String::from("make")),
+            jdk_11: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.to_toml_string().expect("Failed to serialize toml"),
+            "build = \"make\"\njdk11 = true\n"
+        );
+        assert_eq!(
+            config.to_json_string().expect("Failed to serialize json"),
+            "{\n  \"build\": \"make\",\n  \"jdk11\": true\n}"
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_through_every_format() {
+        let temp = TempDir::default();
+        for file_name in [".lift.toml", ".lift.json", ".muse/config"] {
+            let path = temp.join(file_name);
+            std::fs::create_dir_all(path.parent().unwrap()).expect("Failed to create directory");
+            every_field()
+                .write_to_file(&path)
+                .unwrap_or_else(|_| panic!("Failed to write {}", file_name));
+            let actual =
+                Config::from_file(&path).unwrap_or_else(|_| panic!("Failed to read {}", file_name));
+            assert_eq!(actual, every_field());
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn it_should_round_trip_through_yaml() {
+        let yaml = every_field()
+            .to_yaml_string()
+            .expect("Failed to serialize yaml");
+        let actual: Config = Format::Yaml
+            .parse(Path::new(".lift.yaml"), &yaml)
+            .expect("Failed to parse yaml");
+        assert_eq!(actual, every_field());
+    }
+
+    #[test]
+    fn it_should_check_files_against_ignore_files() {
+        let config = Config {
+            ignore_files: Some(String::from("build/\nsrc/test/*\n!src/test/fixtures/")),
+            ..Default::default()
+        };
+        assert!(config.is_file_ignored("build/libs/app.jar"));
+        assert!(config.is_file_ignored("src/test/MainTest.java"));
+        assert!(!config.is_file_ignored("src/test/fixtures/data.json"));
+        assert!(!config.is_file_ignored("src/main/Main.java"));
+        assert!(!Config::default().is_file_ignored("build/libs/app.jar"));
+    }
+
+    #[test]
+    fn it_should_classify_rules() {
+        let config = Config {
+            important_rules: Some(vec![String::from("NULL_*")]),
+            ignore_rules: Some(vec![String::from("NULL_TEST_ASSERTION")]),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.classify_rule("NULL_DEREFERENCE"),
+            RuleDisposition::Important
+        );
+        assert_eq!(
+            config.classify_rule("NULL_TEST_ASSERTION"),
+            RuleDisposition::Ignored
+        );
+        assert_eq!(
+            config.classify_rule("RESOURCE_LEAK"),
+            RuleDisposition::Normal
+        );
+    }
+
+    #[test]
+    fn it_should_resolve_the_effective_tools() {
+        let config = Config {
+            disable_tools: Some(vec![String::from("errorprone")]),
+            custom_tools: Some(vec![String::from("my-linter")]),
+            ..Default::default()
+        };
+        let enabled: Vec<String> = config
+            .effective_tools(&ToolRegistry::lift())
+            .into_iter()
+            .filter(|selection| selection.enabled)
+            .map(|selection| selection.name)
+            .collect();
+        assert_eq!(
+            enabled,
+            vec![
+                "infer",
+                "eslint",
+                "hlint",
+                "findsecbugs",
+                "clippy",
+                "shellcheck",
+                "my-linter"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_merge_scalars_by_overriding() {
+        let base = Config {
+            setup: Some(String::from("base setup")),
+            build: Some(String::from("base build")),
+            jdk_11: Some(true),
+            ..Default::default()
+        };
+        let other = Config {
+            build: Some(String::from("other build")),
+            jdk_11: Some(false),
+            android_version: Some(28),
+            ..Default::default()
+        };
+        let expected = Config {
+            setup: Some(String::from("base setup")),
+            build: Some(String::from("other build")),
+            jdk_11: Some(false),
+            android_version: Some(28),
+            ..Default::default()
+        };
+        assert_eq!(base.merge(other), expected);
+    }
+
+    #[test]
+    fn it_should_merge_lists_by_replacing_or_concatenating() {
+        let base = Config {
+            important_rules: Some(vec![String::from("A"), String::from("B")]),
+            ignore_files: Some(String::from("build/")),
+            tools: Some(vec![String::from("infer"), String::from("eslint")]),
+            allow: Some(vec![String::from("jill")]),
+            ..Default::default()
+        };
+        let other = Config {
+            important_rules: Some(vec![String::from("B"), String::from("C")]),
+            ignore_files: Some(String::from("src/test/")),
+            tools: Some(vec![String::from("eslint")]),
+            ..Default::default()
+        };
+        let expected = Config {
+            important_rules: Some(vec![
+                String::from("A"),
+                String::from("B"),
+                String::from("C"),
+            ]),
+            ignore_files: Some(String::from("build/\nsrc/test/")),
+            tools: Some(vec![String::from("eslint")]),
+            allow: Some(vec![String::from("jill")]),
+            ..Default::default()
+        };
+        assert_eq!(base.merge(other), expected);
+    }
+
+    #[test]
+    fn it_should_override_a_config_with_variables() {
+        let base = Config {
+            build: Some(String::from("make")),
+            important_rules: Some(vec![String::from("A")]),
+            summary_comments: Some(true),
+            jdk_11: Some(true),
+            ..Default::default()
+        };
+        let overrides = Config::from_vars(
+            DEFAULT_ENV_PREFIX,
+            [
+                ("LIFT_BUILD", "make ci"),
+                ("LIFT_IMPORTANT_RULES", "B,C"),
+                ("LIFT_SUMMARY_COMMENTS", "false"),
+            ],
+        )
+        .expect("Failed to read the variables");
+        let expected = Config {
+            build: Some(String::from("make ci")),
+            important_rules: Some(vec![String::from("B"), String::from("C")]),
+            summary_comments: Some(false),
+            jdk_11: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(base.overridden_by(overrides), expected);
+    }
+
+    #[test]
+    fn it_should_interpolate_setup_and_build() {
+        let config = Config {
+            setup: Some(String::from("${LIFT_ROOT}/.lift/deps.sh")),
+            build: Some(String::from("build ${PROJECT} $$HOME")),
+            ignore_files: Some(String::from("${LIFT_ROOT}")),
+            ..Default::default()
+        };
+        let vars = HashMap::from([
+            (String::from("LIFT_ROOT"), String::from("/src")),
+            (String::from("PROJECT"), String::from("configinator")),
+        ]);
+        let expected = Config {
+            setup: Some(String::from("/src/.lift/deps.sh")),
+            build: Some(String::from("build configinator $HOME")),
+            ignore_files: Some(String::from("${LIFT_ROOT}")),
+            ..Default::default()
+        };
+        assert_eq!(
+            config
+                .interpolate_with(|name| vars.get(name).cloned())
+                .expect("Failed to interpolate"),
+            expected
+        );
+    }
+
+    #[test]
+    fn it_should_fail_to_interpolate_undefined_variables() {
+        let config = Config {
+            build: Some(String::from("gradlew -p ${PROJECT_DIR} assemble")),
+            ..Default::default()
+        };
+        let error = config
+            .interpolate_with(|_name| None)
+            .expect_err("This should have failed");
+        if let ConfigError::UndefinedVariable { key, name } = &error {
+            assert_eq!((key.as_str(), name.as_str()), ("build", "PROJECT_DIR"));
+        } else {
+            panic!("Expected an UndefinedVariable, but got {:?}", error);
+        }
+    }
+
+    #[test]
+    fn it_should_merge_every_config_in_a_folder() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".lift")).expect("Failed to create .lift directory");
+        std::fs::write(
+            temp.join(".lift/config.toml"),
+            "build = \"gradlew assemble\"\nignoreRules = [\"B\"]",
+        )
+        .expect("Failed to write .lift/config.toml");
+        std::fs::write(
+            temp.join(".muse.toml"),
+            "setup = \"./deps.sh\"\nbuild = \"make\"\nignoreRules = [\"A\"]",
+        )
+        .expect("Failed to write .muse.toml");
+
+        let expected = Config {
+            setup: Some(String::from("./deps.sh")),
+            build: Some(String::from("gradlew assemble")),
+            ignore_rules: Some(vec![String::from("A"), String::from("B")]),
+            ..Default::default()
+        };
+        let actual = Config::from_folder_merged(&temp).expect("Failed to merge configs");
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn it_should_find_the_nearest_config_in_an_ancestor() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".git")).expect("Failed to create .git directory");
+        std::fs::create_dir_all(temp.join("services/api")).expect("Failed to create subdirectory");
+        std::fs::write(temp.join(".lift.toml"), "build = \"make\"")
+            .expect("Failed to write .lift.toml");
+
+        let expected = AncestorConfig {
+            config: Config {
+                build: Some(String::from("make")),
+                ..Default::default()
+            },
+            directories: vec![temp.canonicalize().unwrap()],
+        };
+        let actual = Config::from_ancestors(temp.join("services/api"), None)
+            .expect("Failed to search ancestors");
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn it_should_pass_over_folders_with_only_an_ignores_file() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".git")).expect("Failed to create .git directory");
+        std::fs::create_dir_all(temp.join(".muse")).expect("Failed to create .muse directory");
+        std::fs::create_dir_all(temp.join("services/.muse"))
+            .expect("Failed to create services/.muse directory");
+        std::fs::write(temp.join(".lift.toml"), "build = \"make\"")
+            .expect("Failed to write .lift.toml");
+        std::fs::write(temp.join(".muse/ignoreFiles"), "target/\n")
+            .expect("Failed to write .muse/ignoreFiles");
+        std::fs::write(temp.join("services/.muse/ignoreFiles"), "dist/\n")
+            .expect("Failed to write services/.muse/ignoreFiles");
+
+        let expected = AncestorConfig {
+            config: Config {
+                build: Some(String::from("make")),
+                ignore_files: Some(String::from("target/")),
+                ..Default::default()
+            },
+            directories: vec![temp.canonicalize().unwrap()],
+        };
+        let actual = Config::from_ancestors(temp.join("services"), None)
+            .expect("Failed to search ancestors");
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn it_should_layer_nearer_configs_over_outer_ones() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".git")).expect("Failed to create .git directory");
+        std::fs::create_dir_all(temp.join("services/api")).expect("Failed to create subdirectory");
+        std::fs::write(
+            temp.join(".lift.toml"),
+            "setup = \"./deps.sh\"\nbuild = \"make\"",
+        )
+        .expect("Failed to write .lift.toml");
+        std::fs::write(
+            temp.join("services/.lift.toml"),
+            "build = \"npm run build\"",
+        )
+        .expect("Failed to write services/.lift.toml");
+
+        let root = temp.canonicalize().unwrap();
+        let expected = AncestorConfig {
+            config: Config {
+                setup: Some(String::from("./deps.sh")),
+                build: Some(String::from("npm run build")),
+                ..Default::default()
+            },
+            directories: vec![root.join("services"), root],
+        };
+        let actual = Config::from_ancestors_layered(temp.join("services/api"), None)
+            .expect("Failed to search ancestors");
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn it_should_anchor_nearer_ignore_files_to_their_folder() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".git")).expect("Failed to create .git directory");
+        std::fs::create_dir_all(temp.join("services/api")).expect("Failed to create subdirectory");
+        std::fs::write(temp.join(".lift.toml"), "ignoreFiles = \"/build/\"")
+            .expect("Failed to write .lift.toml");
+        std::fs::write(
+            temp.join("services/.lift.toml"),
+            "ignoreFiles = \"/generated/\\nsrc/test/\"",
+        )
+        .expect("Failed to write services/.lift.toml");
+
+        let actual = Config::from_ancestors_layered(temp.join("services/api"), None)
+            .expect("Failed to search ancestors")
+            .expect("Expected a config");
+        assert!(actual.config.is_file_ignored("build/out.jar"));
+        assert!(actual.config.is_file_ignored("services/generated/X.java"));
+        assert!(actual.config.is_file_ignored("services/src/test/A.java"));
+        assert!(!actual.config.is_file_ignored("generated/X.java"));
+        assert!(!actual.config.is_file_ignored("services/build/out.jar"));
+    }
+
+    #[test]
+    fn it_should_ignore_a_ceiling_that_does_not_exist() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".git")).expect("Failed to create .git directory");
+        std::fs::create_dir_all(temp.join("services/api")).expect("Failed to create subdirectory");
+        std::fs::write(temp.join(".lift.toml"), "build = \"make\"")
+            .expect("Failed to write .lift.toml");
+
+        let ceiling = temp.join("missing");
+        let actual = Config::from_ancestors(temp.join("services/api"), Some(&ceiling))
+            .expect("Failed to search ancestors")
+            .expect("Expected a config");
+        assert_eq!(actual.config.build.as_deref(), Some("make"));
+    }
+
+    #[test]
+    fn it_should_append_the_ignores_file_to_inline_ignore_files() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".muse")).expect("Failed to create .muse directory");
+        std::fs::write(temp.join(".lift.toml"), "ignoreFiles = \"build/\"")
+            .expect("Failed to write .lift.toml");
+        std::fs::write(
+            temp.join(".muse/ignoreFiles"),
+            "  src/test/\n\n  **/generated/**  \n",
+        )
+        .expect("Failed to write .muse/ignoreFiles");
+
+        let expected = Config {
+            ignore_files: Some(String::from("build/\nsrc/test/\n**/generated/**")),
+            ..Default::default()
+        };
+        let actual = Config::from_folder(&temp).expect("Failed to parse folder");
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn it_should_load_the_ignores_file_without_a_config() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".muse")).expect("Failed to create .muse directory");
+        std::fs::write(temp.join(".muse/ignoreFiles"), "build/\n")
+            .expect("Failed to write .muse/ignoreFiles");
+
+        let expected = Config {
+            ignore_files: Some(String::from("build/")),
+            ..Default::default()
+        };
+        let actual = Config::from_folder_merged(&temp).expect("Failed to parse folder");
+        assert_eq!(actual, Some(expected));
+    }
+
     #[test]
     fn it_should_not_fail_if_there_are_no_configs() {
         let actual =
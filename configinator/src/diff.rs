@@ -0,0 +1,274 @@
+use std::fmt;
+
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::format::Format;
+use crate::keys::KEYS;
+
+/// The differences between two configurations, as found by [`Config::diff`].
+///
+/// Displaying it gives one line per changed key, such as `tools: +eslint, -infer` or
+/// `jdk11: false → true`. It serializes as `{"changes": [...]}`, with each change tagged by its
+/// `kind`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigDiff {
+    pub changes: Vec<FieldChange>,
+}
+
+/// A change to a single key.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// The key that changed, as written in configuration files.
+    pub key: &'static str,
+
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+/// How the value of a key changed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Change {
+    /// A list of strings that is set on both sides, compared as a set: the entries only in the new list
+    /// are added and those only in the old list are removed, regardless of order. The patterns of
+    /// `ignoreFiles` are compared the same way, line by line.
+    List {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+
+    /// Any other change, including a list being set or unset, with `None` for a key that is
+    /// not set.
+    Value {
+        before: Option<Value>,
+        after: Option<Value>,
+    },
+}
+
+impl ConfigDiff {
+    /// Whether the two configurations are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn to_json_string(&self) -> Result<String, ConfigError> {
+        Format::Json.to_string(self)
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.key)?;
+        match &self.change {
+            Change::List { added, removed } => {
+                let entries = added
+                    .iter()
+                    .map(|entry| format!("+{}", entry))
+                    .chain(removed.iter().map(|entry| format!("-{}", entry)));
+                write!(f, "{}", entries.format(", "))
+            }
+            Change::Value { before, after } => {
+                write!(f, "{} → {}", describe(before), describe(after))
+            }
+        }
+    }
+}
+
+fn describe(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => String::from("unset"),
+    }
+}
+
+/// Compares each key of `before` and `after`, in the order of [`KEYS`].
+pub(crate) fn diff(before: &Config, after: &Config) -> ConfigDiff {
+    let before = serde_json::to_value(before).expect("a Config always serializes to JSON");
+    let after = serde_json::to_value(after).expect("a Config always serializes to JSON");
+    let changes = KEYS
+        .iter()
+        .filter_map(|key| {
+            let change = match (before.get(key), after.get(key)) {
+                (Some(Value::Array(before)), Some(Value::Array(after)))
+                    if is_strings(before) && is_strings(after) =>
+                {
+                    compare_lists(before, after)?
+                }
+                (Some(Value::String(before)), Some(Value::String(after)))
+                    if *key == "ignoreFiles" =>
+                {
+                    compare_lists(&lines(before), &lines(after))?
+                }
+                (before, after) if before == after => return None,
+                (before, after) => Change::Value {
+                    before: before.cloned(),
+                    after: after.cloned(),
+                },
+            };
+            Some(FieldChange { key, change })
+        })
+        .collect();
+    ConfigDiff { changes }
+}
+
+/// Compares two lists of strings as sets, or `None` if they hold the same entries.
+fn compare_lists(before: &[Value], after: &[Value]) -> Option<Change> {
+    let added = difference(after, before);
+    let removed = difference(before, after);
+    if added.is_empty() && removed.is_empty() {
+        None
+    } else {
+        Some(Change::List { added, removed })
+    }
+}
+
+/// The lines of a string, such as the patterns of `ignoreFiles`, as a list.
+fn lines(value: &str) -> Vec<Value> {
+    value.lines().map(Value::from).collect()
+}
+
+/// Whether a list holds strings, as all lists but `overrides` do.
+fn is_strings(values: &[Value]) -> bool {
+    values.iter().all(Value::is_string)
+}
+
+/// The entries of `values` that are not in `other`, in order and without duplicates.
+fn difference(values: &[Value], other: &[Value]) -> Vec<String> {
+    values
+        .iter()
+        .filter(|value| !other.contains(value))
+        .filter_map(|value| value.as_str().map(String::from))
+        .unique()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|value| String::from(*value)).collect())
+    }
+
+    #[test]
+    fn it_compares_lists_as_sets() {
+        let before = Config {
+            tools: strings(&["infer", "clippy"]),
+            allow: strings(&["amy", "jill"]),
+            ..Default::default()
+        };
+        let after = Config {
+            tools: strings(&["clippy", "eslint", "eslint"]),
+            allow: strings(&["jill", "amy"]),
+            ..Default::default()
+        };
+        let diff = diff(&before, &after);
+        assert_eq!(
+            diff.changes,
+            vec![FieldChange {
+                key: "tools",
+                change: Change::List {
+                    added: vec![String::from("eslint")],
+                    removed: vec![String::from("infer")],
+                },
+            }]
+        );
+        assert_eq!(diff.to_string(), "tools: +eslint, -infer\n");
+    }
+
+    #[test]
+    fn it_compares_ignore_files_line_by_line() {
+        let before = Config {
+            ignore_files: Some(String::from("build/\ntarget/\n*.log")),
+            ..Default::default()
+        };
+        let after = Config {
+            ignore_files: Some(String::from("*.log\nbuild/\ndist/")),
+            ..Default::default()
+        };
+        assert_eq!(
+            diff(&before, &after).to_string(),
+            "ignoreFiles: +dist/, -target/\n"
+        );
+        let reordered = Config {
+            ignore_files: Some(String::from("target/\n*.log\nbuild/")),
+            ..Default::default()
+        };
+        assert!(diff(&before, &reordered).is_empty());
+    }
+
+    #[test]
+    fn it_describes_changed_values() {
+        let before = Config {
+            jdk_11: Some(false),
+            build: Some(String::from("make")),
+            ignore_rules: strings(&["A"]),
+            ..Default::default()
+        };
+        let after = Config {
+            jdk_11: Some(true),
+            setup: Some(String::from("./deps.sh")),
+            ..Default::default()
+        };
+        assert_eq!(
+            diff(&before, &after).to_string(),
+            "setup: unset → \"./deps.sh\"\n\
+             build: \"make\" → unset\n\
+             ignoreRules: [\"A\"] → unset\n\
+             jdk11: false → true\n"
+        );
+    }
+
+    #[test]
+    fn it_finds_nothing_between_equivalent_configs() {
+        let config = Config {
+            tools: strings(&["infer"]),
+            ..Default::default()
+        };
+        let diff = diff(&config, &config);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "");
+    }
+
+    #[test]
+    fn it_serializes_as_json() {
+        let before = Config {
+            tools: strings(&["infer"]),
+            jdk_11: Some(false),
+            ..Default::default()
+        };
+        let after = Config {
+            tools: strings(&["eslint"]),
+            jdk_11: Some(true),
+            ..Default::default()
+        };
+        let json: Value = serde_json::from_str(
+            &diff(&before, &after)
+                .to_json_string()
+                .expect("Failed to serialize the diff"),
+        )
+        .expect("Failed to parse the diff");
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "changes": [
+                    {"key": "tools", "kind": "list", "added": ["eslint"], "removed": ["infer"]},
+                    {"key": "jdk11", "kind": "value", "before": false, "after": true},
+                ]
+            })
+        );
+    }
+}
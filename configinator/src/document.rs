@@ -0,0 +1,586 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use toml_edit::{Array, DocumentMut, Item, Key, Table, Value};
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::extends::reject_nested_extends;
+use crate::format::{toml_edit_error, Format};
+use crate::keys::DEPRECATED_KEYS;
+use crate::overrides::Override;
+
+/// A TOML configuration file that can be edited without disturbing its comments or layout.
+///
+/// Each setter only touches its own key: setting a value replaces it in place (keeping any
+/// comment on the same line), setting `None` removes the key, and keys that are not yet in the
+/// file are appended to the end of it. Everything else is written back exactly as it was read.
+#[derive(Debug)]
+pub struct ConfigDocument {
+    path: PathBuf,
+    document: DocumentMut,
+}
+
+impl ConfigDocument {
+    /// Loads a configuration file for editing. Only TOML files can be edited.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        if !(path.exists() && path.is_file()) {
+            return Err(ConfigError::FileNotFound(path.to_path_buf()));
+        }
+        match Format::from_path(path) {
+            Some(Format::Toml) | None => {
+                let contents = std::fs::read_to_string(path)?;
+                Ok(ConfigDocument {
+                    path: path.to_path_buf(),
+                    document: contents
+                        .parse()
+                        .map_err(|e| toml_edit_error(path, &contents, e))?,
+                })
+            }
+            Some(format) => Err(ConfigError::EditingUnsupported(format)),
+        }
+    }
+
+    /// Parses the document, including any unsaved edits, into a [`Config`].
+    pub fn config(&self) -> Result<Config, ConfigError> {
+        Format::Toml.parse_config(&self.path, &self.document.to_string())
+    }
+
+    /// Writes the document, including any edits, back to the file it was loaded from.
+    pub fn save(&self) -> Result<(), ConfigError> {
+        std::fs::write(&self.path, self.document.to_string()).map_err(Into::into)
+    }
+
+    pub fn set_setup(&mut self, setup: Option<String>) {
+        self.set_string("setup", setup);
+    }
+
+    pub fn set_build(&mut self, build: Option<String>) {
+        self.set_string("build", build);
+    }
+
+    pub fn set_important_rules(&mut self, important_rules: Option<Vec<String>>) {
+        self.set_list("importantRules", important_rules);
+    }
+
+    pub fn set_ignore_rules(&mut self, ignore_rules: Option<Vec<String>>) {
+        self.set_list("ignoreRules", ignore_rules);
+    }
+
+    /// Sets `ignoreFiles`, writing values with several lines as a multi-line string.
+    pub fn set_ignore_files(&mut self, ignore_files: Option<String>) {
+        self.set_string("ignoreFiles", ignore_files);
+    }
+
+    pub fn set_tools(&mut self, tools: Option<Vec<String>>) {
+        self.set_list("tools", tools);
+    }
+
+    pub fn set_disable_tools(&mut self, disable_tools: Option<Vec<String>>) {
+        self.set_list("disableTools", disable_tools);
+    }
+
+    pub fn set_custom_tools(&mut self, custom_tools: Option<Vec<String>>) {
+        self.set_list("customTools", custom_tools);
+    }
+
+    pub fn set_allow(&mut self, allow: Option<Vec<String>>) {
+        self.set_list("allow", allow);
+    }
+
+    pub fn set_jdk_11(&mut self, jdk_11: Option<bool>) {
+        self.set_value("jdk11", jdk_11.map(Value::from));
+    }
+
+    pub fn set_android_version(&mut self, android_version: Option<u32>) {
+        self.set_value(
+            "androidVersion",
+            android_version.map(|v| Value::from(i64::from(v))),
+        );
+    }
+
+    pub fn set_errorprone_bug_patterns(&mut self, errorprone_bug_patterns: Option<Vec<String>>) {
+        self.set_list("errorproneBugPatterns", errorprone_bug_patterns);
+    }
+
+    pub fn set_summary_comments(&mut self, summary_comments: Option<bool>) {
+        self.set_value("summaryComments", summary_comments.map(Value::from));
+    }
+
+    /// Sets `extends`, the files this one is layered over, written as a list of paths.
+    pub fn set_extends(&mut self, extends: Option<Vec<String>>) {
+        self.set_list("extends", extends);
+    }
+
+    /// Sets the `[profile.<name>]` table, replacing all of its settings, or removes it.
+    ///
+    /// The table keeps its place in the file and the comments before its header, but the
+    /// comments within it are not kept. Fails if the profile sets `extends`, which only the top
+    /// level of a file can use, or with [`ConfigError::EditingUnsupported`] if `profile` is
+    /// written as an inline table.
+    pub fn set_profile(&mut self, name: &str, profile: Option<Config>) -> Result<(), ConfigError> {
+        let profile = match profile {
+            Some(profile) => profile,
+            None => {
+                let profiles = self
+                    .document
+                    .get_mut("profile")
+                    .and_then(Item::as_table_like_mut);
+                if let Some(profiles) = profiles {
+                    profiles.remove(name);
+                    if profiles.is_empty() {
+                        self.document.remove("profile");
+                    }
+                }
+                return Ok(());
+            }
+        };
+        let wrapped = Config {
+            profile: Some(BTreeMap::from([(String::from(name), profile)])),
+            ..Default::default()
+        };
+        reject_nested_extends(&self.path, &wrapped)?;
+        let mut edited = self.serialized(&wrapped)?;
+        let mut table = match edited.remove("profile").map(Item::into_table) {
+            Some(Ok(mut profiles)) => match profiles.remove(name).map(Item::into_table) {
+                Some(Ok(table)) => table,
+                _ => Table::new(),
+            },
+            _ => Table::new(),
+        };
+        let profiles = self
+            .document
+            .entry("profile")
+            .or_insert_with(|| {
+                let mut profiles = Table::new();
+                profiles.set_implicit(true);
+                Item::Table(profiles)
+            })
+            .as_table_mut();
+        match profiles {
+            Some(profiles) => {
+                match profiles.get(name).and_then(Item::as_table) {
+                    Some(existing) => {
+                        *table.decor_mut() = existing.decor().clone();
+                        table.set_position(existing.position().unwrap_or_default());
+                    }
+                    None => table.decor_mut().set_prefix("\n"),
+                }
+                profiles.insert(name, Item::Table(table));
+                Ok(())
+            }
+            None => Err(ConfigError::EditingUnsupported(Format::Toml)),
+        }
+    }
+
+    /// Sets the `[[overrides]]` tables, replacing all of them, or removes them.
+    ///
+    /// The comments within the tables are not kept. Fails if an override sets `extends`, which
+    /// only the top level of a file can use.
+    pub fn set_overrides(&mut self, overrides: Option<Vec<Override>>) -> Result<(), ConfigError> {
+        let overrides = match overrides {
+            Some(overrides) => overrides,
+            None => {
+                self.document.remove("overrides");
+                return Ok(());
+            }
+        };
+        let wrapped = Config {
+            overrides: Some(overrides),
+            ..Default::default()
+        };
+        reject_nested_extends(&self.path, &wrapped)?;
+        let mut edited = self.serialized(&wrapped)?;
+        let first = self
+            .document
+            .get("overrides")
+            .and_then(Item::as_array_of_tables)
+            .and_then(|existing| existing.get(0))
+            .map(|first| (first.position(), first.decor().clone()));
+        match edited.remove("overrides") {
+            Some(Item::ArrayOfTables(mut tables)) => {
+                // The tables stay where the ones they replace were, each separated from the one
+                // before it by a blank line.
+                for (index, table) in tables.iter_mut().enumerate() {
+                    match &first {
+                        Some((position, decor)) => {
+                            if index == 0 {
+                                *table.decor_mut() = decor.clone();
+                            } else {
+                                table.decor_mut().set_prefix("\n");
+                            }
+                            if let Some(position) = position {
+                                table.set_position(*position);
+                            }
+                        }
+                        None => table.decor_mut().set_prefix("\n"),
+                    }
+                }
+                self.document
+                    .insert("overrides", Item::ArrayOfTables(tables));
+            }
+            _ => {
+                self.document.remove("overrides");
+            }
+        }
+        Ok(())
+    }
+
+    /// Serializes a configuration into a table that can be moved into the document.
+    fn serialized(&self, config: &Config) -> Result<Table, ConfigError> {
+        let contents = Format::Toml.to_string(config)?;
+        let edited: DocumentMut = contents
+            .parse()
+            .map_err(|e| toml_edit_error(&self.path, &contents, e))?;
+        Ok(edited.as_table().clone())
+    }
+
+    fn set_string(&mut self, key: &str, value: Option<String>) {
+        self.set_value(key, value.map(|value| string_value(&value)));
+    }
+
+    /// Edits an existing array in place when possible, so that its layout and the comments
+    /// between its items survive adding or removing entries.
+    fn set_list(&mut self, key: &str, values: Option<Vec<String>>) {
+        self.migrate_key(key);
+        if let (Some(values), Some(array)) = (
+            &values,
+            self.document.get_mut(key).and_then(Item::as_array_mut),
+        ) {
+            array.retain(|item| {
+                item.as_str()
+                    .is_some_and(|item| values.iter().any(|v| v == item))
+            });
+            for value in values {
+                if !array.iter().any(|item| item.as_str() == Some(value)) {
+                    array.push(value.as_str());
+                }
+            }
+            if array
+                .iter()
+                .map(Value::as_str)
+                .eq(values.iter().map(|v| Some(v.as_str())))
+            {
+                return;
+            }
+        }
+        self.set_value(
+            key,
+            values.map(|values| Value::Array(Array::from_iter(values))),
+        );
+    }
+
+    fn set_value(&mut self, key: &str, value: Option<Value>) {
+        self.migrate_key(key);
+        match value {
+            Some(mut value) => match self.document.get_mut(key).and_then(Item::as_value_mut) {
+                Some(existing) => {
+                    *value.decor_mut() = existing.decor().clone();
+                    *existing = value;
+                }
+                None => {
+                    self.document.insert(key, toml_edit::value(value));
+                }
+            },
+            None => {
+                self.document.remove(key);
+            }
+        }
+    }
+
+    /// Renames the legacy spelling of `key`, if the document uses it, so that editing the key
+    /// does not leave both spellings in the file. The key keeps its place and its comments.
+    fn migrate_key(&mut self, key: &str) {
+        let legacy = DEPRECATED_KEYS
+            .iter()
+            .find(|(_deprecated, replacement)| *replacement == key);
+        let deprecated = match legacy {
+            Some((deprecated, _replacement)) if self.document.contains_key(deprecated) => {
+                *deprecated
+            }
+            _ => return,
+        };
+        if self.document.contains_key(key) {
+            self.document.remove(deprecated);
+            return;
+        }
+        // Tables cannot rename their keys, so every entry is taken out and put back in order.
+        let table = self.document.as_table_mut();
+        let names: Vec<String> = table
+            .iter()
+            .map(|(name, _item)| String::from(name))
+            .collect();
+        for name in names {
+            if let Some((mut entry_key, item)) = table.remove_entry(&name) {
+                if name == deprecated {
+                    let mut renamed = Key::new(key);
+                    *renamed.leaf_decor_mut() = entry_key.leaf_decor().clone();
+                    *renamed.dotted_decor_mut() = entry_key.dotted_decor().clone();
+                    entry_key = renamed;
+                }
+                table.insert_formatted(&entry_key, item);
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+/// Uses a multi-line literal string for values with several lines, as in the Lift documentation.
+fn string_value(value: &str) -> Value {
+    if value.contains('\n') && !value.contains("'''") {
+        if let Ok(multiline) = format!("'''\n{}\n'''", value).parse() {
+            return multiline;
+        }
+    }
+    Value::from(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_testdir::TempDir;
+
+    const EXAMPLE: &str = r#"
+setup     = ".lift/script_that_downloads_deps.sh"
+build     = "gradlew assemble"
+
+# We only care about NULL_DEREFERENCE (from Infer)
+# and no-extra-boolean-cast (from ESLint)
+importantRules = ["NULL_DEREFERENCE", "no-extra-boolean-cast"]
+
+# Ignore results from test and build directories
+ignoreFiles = """
+            build/
+            src/test/
+            """
+
+# Only run infer and eslint (do not run errorprone, hlint, findsecbugs)
+tools = [ "infer", "eslint" ] # for now
+
+jdk11 = false
+"#;
+
+    fn load_example(temp: &TempDir) -> ConfigDocument {
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, EXAMPLE).expect("Failed to write .lift.toml");
+        ConfigDocument::from_file(&path).expect("Failed to load .lift.toml")
+    }
+
+    #[test]
+    fn it_should_leave_an_unedited_document_untouched() {
+        let temp = TempDir::default();
+        let document = load_example(&temp);
+        assert_eq!(document.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn it_should_only_change_the_edited_keys() {
+        let temp = TempDir::default();
+        let mut document = load_example(&temp);
+        document.set_important_rules(Some(vec![
+            String::from("NULL_DEREFERENCE"),
+            String::from("no-extra-boolean-cast"),
+            String::from("RESOURCE_LEAK"),
+        ]));
+        document.set_tools(Some(vec![String::from("infer")]));
+        document.set_jdk_11(Some(true));
+        document.set_build(None);
+        document.set_summary_comments(Some(false));
+        document.save().expect("Failed to save .lift.toml");
+
+        let expected = r#"
+setup     = ".lift/script_that_downloads_deps.sh"
+
+# We only care about NULL_DEREFERENCE (from Infer)
+# and no-extra-boolean-cast (from ESLint)
+importantRules = ["NULL_DEREFERENCE", "no-extra-boolean-cast", "RESOURCE_LEAK"]
+
+# Ignore results from test and build directories
+ignoreFiles = """
+            build/
+            src/test/
+            """
+
+# Only run infer and eslint (do not run errorprone, hlint, findsecbugs)
+tools = [ "infer"] # for now
+
+jdk11 = true
+summaryComments = false
+"#;
+        let actual =
+            std::fs::read_to_string(temp.join(".lift.toml")).expect("Failed to read .lift.toml");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn it_should_write_multiline_ignore_files_readably() {
+        let temp = TempDir::default();
+        let mut document = load_example(&temp);
+        document.set_ignore_files(Some(String::from("build/\nsrc/test/\n**/generated/**")));
+
+        let expected = Config {
+            setup: Some(String::from(".lift/script_that_downloads_deps.sh")),
+            build: Some(String::from("gradlew assemble")),
+            important_rules: Some(vec![
+                String::from("NULL_DEREFERENCE"),
+                String::from("no-extra-boolean-cast"),
+            ]),
+            ignore_files: Some(String::from("build/\nsrc/test/\n**/generated/**")),
+            tools: Some(vec![String::from("infer"), String::from("eslint")]),
+            jdk_11: Some(false),
+            ..Default::default()
+        };
+        assert!(document
+            .to_string()
+            .contains("ignoreFiles = '''\nbuild/\nsrc/test/\n**/generated/**\n'''\n"));
+        assert_eq!(
+            document.config().expect("Failed to parse document"),
+            expected
+        );
+    }
+
+    #[test]
+    fn it_should_rename_legacy_keys_when_editing_them() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "jdk_11 = false\nimportant_rules = [\"A\"]\n")
+            .expect("Failed to write .lift.toml");
+        let mut document = ConfigDocument::from_file(&path).expect("Failed to load .lift.toml");
+        document.set_jdk_11(Some(true));
+        document.set_important_rules(Some(vec![String::from("A"), String::from("B")]));
+
+        assert_eq!(
+            document.to_string(),
+            "jdk11 = true\nimportantRules = [\"A\", \"B\"]\n"
+        );
+        assert_eq!(
+            document.config().expect("Failed to parse document"),
+            Config {
+                jdk_11: Some(true),
+                important_rules: Some(vec![String::from("A"), String::from("B")]),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_keep_the_place_and_comments_of_renamed_legacy_keys() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "# keep jdk comment\njdk_11 = false # inline\nbuild = \"make\"\n\n[profile.ci]\nbuild = \"gradle\"\n",
+        )
+        .expect("Failed to write .lift.toml");
+        let mut document = ConfigDocument::from_file(&path).expect("Failed to load .lift.toml");
+        document.set_jdk_11(Some(true));
+
+        assert_eq!(
+            document.to_string(),
+            "# keep jdk comment\njdk11 = true # inline\nbuild = \"make\"\n\n[profile.ci]\nbuild = \"gradle\"\n"
+        );
+    }
+
+    #[test]
+    fn it_should_edit_extends_profiles_and_overrides() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "build = \"make\"\n\n# Runs every night\n[profile.nightly]\njdk11 = true\n\n[profile.ci]\nbuild = \"gradle\"\n",
+        )
+        .expect("Failed to write .lift.toml");
+        let mut document = ConfigDocument::from_file(&path).expect("Failed to load .lift.toml");
+        document.set_extends(Some(vec![String::from("base.toml")]));
+        document
+            .set_profile(
+                "nightly",
+                Some(Config {
+                    tools: Some(vec![String::from("infer")]),
+                    ..Default::default()
+                }),
+            )
+            .expect("Failed to set the profile");
+        document
+            .set_profile("ci", None)
+            .expect("Failed to remove the profile");
+        document
+            .set_overrides(Some(vec![Override {
+                paths: vec![String::from("web/")],
+                config: Config {
+                    tools: Some(vec![String::from("eslint")]),
+                    ..Default::default()
+                },
+            }]))
+            .expect("Failed to set the overrides");
+
+        assert_eq!(
+            document.to_string(),
+            "build = \"make\"\nextends = [\"base.toml\"]\n\n# Runs every night\n[profile.nightly]\ntools = [\"infer\"]\n\n[[overrides]]\npaths = [\"web/\"]\ntools = [\"eslint\"]\n"
+        );
+    }
+
+    #[test]
+    fn it_should_keep_replaced_overrides_in_place() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "[[overrides]]\npaths = [\"web/\"]\njdk11 = true\n\n[profile.ci]\nbuild = \"gradle\"\n",
+        )
+        .expect("Failed to write .lift.toml");
+        let mut document = ConfigDocument::from_file(&path).expect("Failed to load .lift.toml");
+        document
+            .set_overrides(Some(vec![Override {
+                paths: vec![String::from("api/")],
+                config: Config::default(),
+            }]))
+            .expect("Failed to set the overrides");
+
+        assert_eq!(
+            document.to_string(),
+            "[[overrides]]\npaths = [\"api/\"]\n\n[profile.ci]\nbuild = \"gradle\"\n"
+        );
+    }
+
+    #[test]
+    fn it_should_refuse_to_set_extends_in_a_profile_or_override() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "build = \"make\"\n").expect("Failed to write .lift.toml");
+        let mut document = ConfigDocument::from_file(&path).expect("Failed to load .lift.toml");
+        let extending = Config {
+            extends: Some(vec![String::from("base.toml")]),
+            ..Default::default()
+        };
+
+        let error = document
+            .set_profile("nightly", Some(extending.clone()))
+            .expect_err("This should have failed");
+        assert!(matches!(error, ConfigError::NestedExtends { .. }));
+        let error = document
+            .set_overrides(Some(vec![Override {
+                paths: vec![String::from("web/")],
+                config: extending,
+            }]))
+            .expect_err("This should have failed");
+        assert!(matches!(error, ConfigError::NestedExtends { .. }));
+        assert_eq!(document.to_string(), "build = \"make\"\n");
+    }
+
+    #[test]
+    fn it_should_refuse_to_edit_a_json_file() {
+        let error =
+            ConfigDocument::from_file("examples/.lift.json").expect_err("This should have failed");
+        match error {
+            ConfigError::EditingUnsupported(format) => assert_eq!(format, Format::Json),
+            e => panic!("Expected an EditingUnsupported, but got {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::ffi::OsString;
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::keys::KEYS;
+
+/// The prefix that environment variables overriding the configuration have unless another is
+/// given, as in `LIFT_BUILD`.
+pub const DEFAULT_ENV_PREFIX: &str = "LIFT_";
+
+/// Builds a configuration from the variables that start with `prefix`, ignoring all others.
+///
+/// Each key has a variable named after it in upper snake case, such as `LIFT_IMPORTANT_RULES`
+/// for `importantRules` and `LIFT_JDK11` for `jdk11`. Values are parsed according to the key:
+///
+/// * `setup` and `build` are used as they are.
+/// * Lists are split on commas, with each entry trimmed and empty entries dropped, so an empty
+///   variable sets an empty list. `ignoreFiles` is split the same way, one pattern per entry.
+/// * `jdk11` and `summaryComments` accept `true` or `false` in any case, or `1` or `0`.
+/// * `androidVersion` must be a non-negative integer.
+///
+/// Variables with the prefix that do not name a key are ignored, as the prefix may be shared
+/// with other settings.
+pub(crate) fn from_vars<I>(prefix: &str, vars: I) -> Result<Config, ConfigError>
+where
+    I: IntoIterator<Item = (OsString, OsString)>,
+{
+    let mut config = Config::default();
+    for (name, value) in vars {
+        let suffix = match name.to_str().and_then(|name| name.strip_prefix(prefix)) {
+            Some(suffix) => suffix,
+            None => continue,
+        };
+        let invalid = |reason: &str| ConfigError::InvalidEnvironmentVariable {
+            name: name.to_string_lossy().into_owned(),
+            value: value.to_string_lossy().into_owned(),
+            reason: String::from(reason),
+        };
+        let value = match value.to_str() {
+            Some(value) => value,
+            None if is_known(suffix) => return Err(invalid("the value is not valid unicode")),
+            None => continue,
+        };
+        match suffix {
+            "SETUP" => config.setup = Some(String::from(value)),
+            "BUILD" => config.build = Some(String::from(value)),
+            "IMPORTANT_RULES" => config.important_rules = Some(parse_list(value)),
+            "IGNORE_RULES" => config.ignore_rules = Some(parse_list(value)),
+            "IGNORE_FILES" => config.ignore_files = Some(parse_list(value).join("\n")),
+            "TOOLS" => config.tools = Some(parse_list(value)),
+            "DISABLE_TOOLS" => config.disable_tools = Some(parse_list(value)),
+            "CUSTOM_TOOLS" => config.custom_tools = Some(parse_list(value)),
+            "ALLOW" => config.allow = Some(parse_list(value)),
+            "JDK11" => config.jdk_11 = Some(parse_bool(value).ok_or_else(|| invalid(BOOL))?),
+            "ANDROID_VERSION" => {
+                config.android_version = Some(value.trim().parse().map_err(|_| invalid(INTEGER))?)
+            }
+            "ERRORPRONE_BUG_PATTERNS" => config.errorprone_bug_patterns = Some(parse_list(value)),
+            "SUMMARY_COMMENTS" => {
+                config.summary_comments = Some(parse_bool(value).ok_or_else(|| invalid(BOOL))?)
+            }
+            _ => {}
+        }
+    }
+    Ok(config)
+}
+
+const BOOL: &str = "expected `true`, `false`, `1` or `0`";
+const INTEGER: &str = "expected a non-negative integer";
+
+/// The variable names, without the prefix, for every key in the same order as [`KEYS`], except
+/// `overrides`, `profile` and `extends`, which cannot be set from the environment.
+const VARIABLES: &[&str] = &[
+    "SETUP",
+    "BUILD",
+    "IMPORTANT_RULES",
+    "IGNORE_RULES",
+    "IGNORE_FILES",
+    "TOOLS",
+    "DISABLE_TOOLS",
+    "CUSTOM_TOOLS",
+    "ALLOW",
+    "JDK11",
+    "ANDROID_VERSION",
+    "ERRORPRONE_BUG_PATTERNS",
+    "SUMMARY_COMMENTS",
+];
+
+/// The name of the variable that sets `key`, which must be one of [`KEYS`] other than
+/// `overrides`, `profile` and `extends`.
+pub(crate) fn variable_for(prefix: &str, key: &str) -> String {
+    let index = KEYS
+        .iter()
+        .position(|known| *known == key)
+        .expect("Expected a known key");
+    format!("{}{}", prefix, VARIABLES[index])
+}
+
+fn is_known(suffix: &str) -> bool {
+    VARIABLES.contains(&suffix)
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(OsString, OsString)> {
+        vars.iter()
+            .map(|(name, value)| (OsString::from(name), OsString::from(value)))
+            .collect()
+    }
+
+    #[test]
+    fn it_has_a_variable_for_every_key() {
+        let to_variable = |key: &str| {
+            let mut variable = String::new();
+            for c in key.chars() {
+                if c.is_ascii_uppercase() {
+                    variable.push('_');
+                }
+                variable.push(c.to_ascii_uppercase());
+            }
+            variable
+        };
+        let expected: Vec<String> = KEYS
+            .iter()
+            .filter(|key| !["overrides", "profile", "extends"].contains(key))
+            .map(|key| to_variable(key))
+            .collect();
+        assert_eq!(VARIABLES, expected);
+    }
+
+    #[test]
+    fn it_parses_each_kind_of_value() {
+        let config = from_vars(
+            DEFAULT_ENV_PREFIX,
+            vars(&[
+                ("LIFT_BUILD", "make all"),
+                ("LIFT_TOOLS", " infer, eslint,,"),
+                ("LIFT_DISABLE_TOOLS", ""),
+                ("LIFT_IGNORE_FILES", "*.md, target/"),
+                ("LIFT_JDK11", "TRUE"),
+                ("LIFT_SUMMARY_COMMENTS", "0"),
+                ("LIFT_ANDROID_VERSION", "30"),
+                ("LIFT_TOKEN", "secret"),
+                ("PATH", "/usr/bin"),
+            ]),
+        )
+        .expect("Failed to read the variables");
+        let expected = Config {
+            build: Some(String::from("make all")),
+            tools: Some(vec![String::from("infer"), String::from("eslint")]),
+            disable_tools: Some(vec![]),
+            ignore_files: Some(String::from("*.md\ntarget/")),
+            jdk_11: Some(true),
+            summary_comments: Some(false),
+            android_version: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn it_uses_the_given_prefix() {
+        let config = from_vars(
+            "MUSE_",
+            vars(&[("LIFT_SETUP", "lift"), ("MUSE_SETUP", "muse")]),
+        )
+        .expect("Failed to read the variables");
+        assert_eq!(config.setup, Some(String::from("muse")));
+    }
+
+    #[test]
+    fn it_names_the_variable_with_an_invalid_value() {
+        let error = from_vars(DEFAULT_ENV_PREFIX, vars(&[("LIFT_JDK11", "yes")]))
+            .expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            "Invalid value `yes` for environment variable LIFT_JDK11: expected `true`, `false`, `1` or `0`"
+        );
+
+        let error = from_vars(DEFAULT_ENV_PREFIX, vars(&[("LIFT_ANDROID_VERSION", "-1")]))
+            .expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            "Invalid value `-1` for environment variable LIFT_ANDROID_VERSION: expected a non-negative integer"
+        );
+    }
+}
@@ -1,7 +1,14 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use crate::format::Format;
+use crate::location::{render_diagnostic, Location};
+
+/// Everything that can go wrong when loading, editing or writing a configuration.
+///
+/// Optional features, such as `yaml`, add variants, so matches on this must have a wildcard arm.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum ConfigError {
     #[error("Could not find configuration {0:?}")]
     FileNotFound(PathBuf),
@@ -12,6 +19,220 @@ pub enum ConfigError {
     #[error("Could not read configuration file")]
     FileReadFailed(#[from] std::io::Error),
 
-    #[error("Failed to parse file as a toml file")]
-    FileTomlParseFailed(#[from] toml::de::Error),
+    #[error("Failed to search {path:?} for configurations")]
+    TreeSearchFailed {
+        path: PathBuf,
+        #[source]
+        source: ignore::Error,
+    },
+
+    #[error(
+        "Failed to parse file as a toml file at {}: {}",
+        describe_position(path, location),
+        source.message()
+    )]
+    FileTomlParseFailed {
+        path: PathBuf,
+        location: Option<Location>,
+        source: Box<toml::de::Error>,
+    },
+
+    #[error(
+        "Failed to parse file as a json file at {}: {}",
+        describe_position(path, location),
+        json_message(source)
+    )]
+    FileJsonParseFailed {
+        path: PathBuf,
+        location: Option<Location>,
+        source: serde_json::Error,
+    },
+
+    #[cfg(feature = "yaml")]
+    #[error(
+        "Failed to parse file as a yaml file at {}: {}",
+        describe_position(path, location),
+        yaml_message(source)
+    )]
+    FileYamlParseFailed {
+        path: PathBuf,
+        location: Option<Location>,
+        source: serde_yaml::Error,
+    },
+
+    #[error(
+        "Failed to parse file as an editable toml file at {}: {}",
+        describe_position(path, location),
+        source.message()
+    )]
+    FileTomlEditFailed {
+        path: PathBuf,
+        location: Option<Location>,
+        source: Box<toml_edit::TomlError>,
+    },
+
+    #[error("Editing {0} configuration files is not supported")]
+    EditingUnsupported(Format),
+
+    #[error(
+        "Unknown key `{key}`{}{}",
+        describe_location(location),
+        describe_suggestion(suggestion)
+    )]
+    UnknownKey {
+        key: String,
+        location: Option<Location>,
+        suggestion: Option<String>,
+    },
+
+    #[error("Invalid value `{value}` for environment variable {name}: {reason}")]
+    InvalidEnvironmentVariable {
+        name: String,
+        value: String,
+        reason: String,
+    },
+
+    #[error("Undefined variable `{name}` in `{key}`")]
+    UndefinedVariable { key: String, name: String },
+
+    #[error("Invalid interpolation in `{key}`: {reason}")]
+    InvalidInterpolation { key: String, reason: String },
+
+    #[error(
+        "Failed to load a configuration through `extends` ({}): {source}",
+        describe_chain(chain)
+    )]
+    ExtendsFailed {
+        chain: Vec<PathBuf>,
+        source: Box<ConfigError>,
+    },
+
+    #[error("`extends` forms a cycle: {}", describe_chain(chain))]
+    ExtendsCycle { chain: Vec<PathBuf> },
+
+    #[error(
+        "`extends` is nested more than {limit} levels deep: {}",
+        describe_chain(chain)
+    )]
+    ExtendsTooDeep { chain: Vec<PathBuf>, limit: usize },
+
+    #[error(
+        "`extends` can only be set at the top level, but {} sets it in `{table}`",
+        path.display()
+    )]
+    NestedExtends { path: PathBuf, table: String },
+
+    #[error("Unknown profile `{name}`, {}", describe_profiles(available))]
+    UnknownProfile {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("Invalid ignoreFiles pattern")]
+    InvalidIgnorePattern(#[from] ignore::Error),
+
+    #[error("Failed to serialize configuration as toml")]
+    TomlSerializeFailed(#[source] toml::ser::Error),
+
+    #[error("Failed to serialize configuration as json")]
+    JsonSerializeFailed(#[source] serde_json::Error),
+
+    #[cfg(feature = "yaml")]
+    #[error("Failed to serialize configuration as yaml")]
+    YamlSerializeFailed(#[source] serde_yaml::Error),
+
+    #[error("Failed to parse file in any supported format:{}", describe_attempts(.0))]
+    FormatDetectionFailed(Vec<(Format, ConfigError)>),
+}
+
+impl ConfigError {
+    /// Where in a configuration file the problem is, if the error can be pinned to one.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            ConfigError::FileTomlParseFailed { location, .. }
+            | ConfigError::FileJsonParseFailed { location, .. }
+            | ConfigError::FileTomlEditFailed { location, .. }
+            | ConfigError::UnknownKey { location, .. } => location.as_ref(),
+            #[cfg(feature = "yaml")]
+            ConfigError::FileYamlParseFailed { location, .. } => location.as_ref(),
+            ConfigError::ExtendsFailed { source, .. } => source.location(),
+            _ => None,
+        }
+    }
+
+    /// Renders the error like a compiler diagnostic, quoting the offending line of the file with
+    /// a caret under the problem when the error has a location.
+    pub fn render(&self) -> String {
+        render_diagnostic("error", &self.to_string(), self.location())
+    }
+}
+
+fn describe_position(path: &Path, location: &Option<Location>) -> String {
+    match location {
+        Some(location) => location.to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+/// The message of a JSON error, without the line and column that serde_json appends to it.
+fn json_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    let position = format!(" at line {} column {}", e.line(), e.column());
+    match message.strip_suffix(&position) {
+        Some(message) => String::from(message),
+        None => message,
+    }
+}
+
+/// The message of a YAML error, without the line and column that serde_yaml appends to it.
+#[cfg(feature = "yaml")]
+fn yaml_message(e: &serde_yaml::Error) -> String {
+    let message = e.to_string();
+    let position = e
+        .location()
+        .map(|location| format!(" at line {} column {}", location.line(), location.column()))
+        .unwrap_or_default();
+    match message.strip_suffix(&position) {
+        Some(message) => String::from(message),
+        None => message,
+    }
+}
+
+fn describe_location(location: &Option<Location>) -> String {
+    location
+        .as_ref()
+        .map(|location| format!(" at {}", location))
+        .unwrap_or_default()
+}
+
+fn describe_suggestion(suggestion: &Option<String>) -> String {
+    suggestion
+        .as_ref()
+        .map(|suggestion| format!(", did you mean `{}`?", suggestion))
+        .unwrap_or_default()
+}
+
+/// The files in an `extends` chain, from the one that was loaded to the one that failed.
+fn describe_chain(chain: &[PathBuf]) -> String {
+    let files: Vec<String> = chain
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    files.join(" → ")
+}
+
+fn describe_profiles(available: &[String]) -> String {
+    if available.is_empty() {
+        String::from("no profiles are defined")
+    } else {
+        let names: Vec<String> = available.iter().map(|name| format!("`{}`", name)).collect();
+        format!("the available profiles are {}", names.join(", "))
+    }
+}
+
+fn describe_attempts(attempts: &[(Format, ConfigError)]) -> String {
+    attempts
+        .iter()
+        .map(|(format, e)| format!("\n  as {}: {}", format, e))
+        .collect()
 }
@@ -0,0 +1,402 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::loaded::LoadedConfig;
+
+/// How many files deep `extends` may go, counting from the file that was loaded.
+pub(crate) const EXTENDS_DEPTH_LIMIT: usize = 8;
+
+/// Resolves the `extends` of a configuration loaded from `path`, discarding where each value
+/// came from. See [`with_bases`].
+pub(crate) fn resolve_extends(path: &Path, config: Config) -> Result<Config, ConfigError> {
+    if config.extends.is_none() {
+        return Ok(config);
+    }
+    with_bases(path, LoadedConfig::without_origins(config)).map(|loaded| loaded.config)
+}
+
+/// Layers a configuration loaded from `path` over the files it `extends`.
+///
+/// Each entry is resolved relative to the folder of the file that names it, and is loaded with
+/// its own `extends` resolved in turn. The bases are layered with [`LoadedConfig::merge`] in the
+/// order they are listed, and the configuration itself is layered over all of them, so it takes
+/// precedence.
+///
+/// Fails if a file extends itself through any chain of files, or if the chain is more than
+/// [`EXTENDS_DEPTH_LIMIT`] files deep. If a base cannot be loaded, the error lists every file
+/// in the chain that led to it.
+pub(crate) fn with_bases(path: &Path, loaded: LoadedConfig) -> Result<LoadedConfig, ConfigError> {
+    with_bases_loaded_by(path, loaded, &mut LoadedConfig::from_single_file)
+}
+
+/// Layers a configuration over its bases like [`with_bases`], loading each base with `load`.
+pub(crate) fn with_bases_loaded_by<F>(
+    path: &Path,
+    loaded: LoadedConfig,
+    load: &mut F,
+) -> Result<LoadedConfig, ConfigError>
+where
+    F: FnMut(&Path) -> Result<LoadedConfig, ConfigError>,
+{
+    layer_over_bases(path, loaded, &mut vec![path.to_path_buf()], load)
+}
+
+/// Fails if `extends` is set anywhere but the top level of a configuration loaded from `path`,
+/// such as in a `[profile.nightly]` or `[[overrides]]` table, where it would never be resolved.
+pub(crate) fn reject_nested_extends(path: &Path, config: &Config) -> Result<(), ConfigError> {
+    match nested_extends(config, "") {
+        Some(table) => Err(ConfigError::NestedExtends {
+            path: path.to_path_buf(),
+            table,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The first table within `config` that sets `extends`, named by its path from the top level.
+fn nested_extends(config: &Config, prefix: &str) -> Option<String> {
+    let profiles = config
+        .profile
+        .iter()
+        .flatten()
+        .map(|(name, profile)| (format!("{}profile.{}", prefix, name), profile));
+    let overrides = config
+        .overrides
+        .iter()
+        .flatten()
+        .map(|entry| (format!("{}overrides", prefix), &entry.config));
+    profiles.chain(overrides).find_map(|(table, config)| {
+        if config.extends.is_some() {
+            Some(table)
+        } else {
+            nested_extends(config, &format!("{}.", table))
+        }
+    })
+}
+
+/// `chain` holds every file from the one that was loaded down to `path`.
+fn layer_over_bases<F>(
+    path: &Path,
+    mut loaded: LoadedConfig,
+    chain: &mut Vec<PathBuf>,
+    load: &mut F,
+) -> Result<LoadedConfig, ConfigError>
+where
+    F: FnMut(&Path) -> Result<LoadedConfig, ConfigError>,
+{
+    let extends = match loaded.take_extends() {
+        Some(extends) => extends,
+        None => return Ok(loaded),
+    };
+    let folder = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut bases: Option<LoadedConfig> = None;
+    for entry in extends {
+        let base_path = folder.join(entry);
+        let is_cycle = chain.iter().any(|file| is_same_file(file, &base_path));
+        chain.push(base_path.clone());
+        if is_cycle {
+            return Err(ConfigError::ExtendsCycle {
+                chain: chain.clone(),
+            });
+        }
+        if chain.len() > EXTENDS_DEPTH_LIMIT + 1 {
+            return Err(ConfigError::ExtendsTooDeep {
+                chain: chain.clone(),
+                limit: EXTENDS_DEPTH_LIMIT,
+            });
+        }
+        let base = load(&base_path).map_err(|source| ConfigError::ExtendsFailed {
+            chain: chain.clone(),
+            source: Box::new(source),
+        })?;
+        let base = layer_over_bases(&base_path, base, chain, load)?;
+        chain.pop();
+        bases = Some(match bases {
+            Some(bases) => bases.merge(base),
+            None => base,
+        });
+    }
+    Ok(match bases {
+        Some(bases) => bases.merge(loaded),
+        None => loaded,
+    })
+}
+
+/// Compares paths by the file they point to, so that `a/../b.toml` and `b.toml` are the same.
+/// Paths that cannot be resolved, such as those of missing files, are compared as written.
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_testdir::TempDir;
+
+    fn write(root: &Path, relative: &str, contents: &str) -> PathBuf {
+        let path = root.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("Failed to create parent directory");
+        }
+        std::fs::write(&path, contents).expect("Failed to write file");
+        path
+    }
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|value| String::from(*value)).collect())
+    }
+
+    #[test]
+    fn it_should_layer_a_file_over_its_base() {
+        let temp = TempDir::default();
+        write(
+            &temp,
+            "shared/base.toml",
+            "build = \"make\"\nimportantRules = [\"A\"]\nignoreRules = [\"B\"]\n",
+        );
+        let path = write(
+            &temp,
+            "repo/.lift.toml",
+            "extends = \"../shared/base.toml\"\nimportantRules = [\"C\"]\njdk11 = true\n",
+        );
+
+        let config = Config::from_file(&path).expect("Failed to parse file");
+        let expected = Config {
+            build: Some(String::from("make")),
+            important_rules: strings(&["A", "C"]),
+            ignore_rules: strings(&["B"]),
+            jdk_11: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn it_should_layer_a_list_of_bases_in_order() {
+        let temp = TempDir::default();
+        write(
+            &temp,
+            "java.toml",
+            "build = \"gradlew build\"\njdk11 = true\n",
+        );
+        write(&temp, "strict.toml", "build = \"gradlew check\"\n");
+        let path = write(
+            &temp,
+            ".lift.toml",
+            "extends = [\"java.toml\", \"strict.toml\"]\nsetup = \"./deps.sh\"\n",
+        );
+
+        let config = Config::from_file(&path).expect("Failed to parse file");
+        assert_eq!(config.build.as_deref(), Some("gradlew check"));
+        assert_eq!(config.jdk_11, Some(true));
+        assert_eq!(config.extends, None);
+    }
+
+    #[test]
+    fn it_should_resolve_bases_relative_to_the_file_that_names_them() {
+        let temp = TempDir::default();
+        write(&temp, "shared/rules.toml", "ignoreRules = [\"A\"]\n");
+        write(&temp, "shared/base.toml", "extends = \"rules.toml\"\n");
+        let path = write(
+            &temp,
+            "repo/.lift.toml",
+            "extends = \"../shared/base.toml\"\nbuild = \"make\"\n",
+        );
+
+        let loaded = LoadedConfig::from_file(&path).expect("Failed to load file");
+        assert_eq!(loaded.config.ignore_rules, strings(&["A"]));
+        let origin = loaded
+            .origin_of("ignoreRules")
+            .and_then(|origin| origin.path())
+            .expect("Expected an origin");
+        assert_eq!(origin, temp.join("repo/../shared/rules.toml"));
+        assert_eq!(loaded.origin_of("extends"), None);
+    }
+
+    #[test]
+    fn it_should_detect_cycles() {
+        let temp = TempDir::default();
+        write(&temp, "b.toml", "extends = \"c.toml\"\n");
+        write(&temp, "c.toml", "extends = \"./b.toml\"\n");
+        let path = write(&temp, "a.toml", "extends = \"b.toml\"\n");
+
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        let chain = |files: &[&str]| {
+            files
+                .iter()
+                .map(|file| temp.join(file).display().to_string())
+                .collect::<Vec<_>>()
+                .join(" → ")
+        };
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "`extends` forms a cycle: {}",
+                chain(&["a.toml", "b.toml", "c.toml", "./b.toml"])
+            )
+        );
+    }
+
+    #[test]
+    fn it_should_limit_the_depth() {
+        let temp = TempDir::default();
+        for depth in 0..=EXTENDS_DEPTH_LIMIT {
+            write(
+                &temp,
+                &format!("{}.toml", depth),
+                &format!("extends = \"{}.toml\"\n", depth + 1),
+            );
+        }
+        write(
+            &temp,
+            &format!("{}.toml", EXTENDS_DEPTH_LIMIT + 1),
+            "build = \"make\"\n",
+        );
+
+        let error = Config::from_file(temp.join("0.toml")).expect_err("This should have failed");
+        match error {
+            ConfigError::ExtendsTooDeep { chain, limit } => {
+                assert_eq!(limit, EXTENDS_DEPTH_LIMIT);
+                assert_eq!(chain.len(), EXTENDS_DEPTH_LIMIT + 2);
+            }
+            e => panic!("Expected an ExtendsTooDeep, but got {:?}", e),
+        }
+
+        let config = Config::from_file(temp.join("1.toml")).expect("Failed to parse file");
+        assert_eq!(config.build.as_deref(), Some("make"));
+    }
+
+    #[test]
+    fn it_should_warn_about_every_file_in_the_chain() {
+        let temp = TempDir::default();
+        let base = write(
+            &temp,
+            "shared/base.toml",
+            "build = \"make\"\n\nimportantRule = [\"A\"]\n",
+        );
+        let path = write(
+            &temp,
+            "repo/.lift.toml",
+            "extends = \"../shared/base.toml\"\njdk_11 = true\n",
+        );
+
+        let report = Config::from_file_with_warnings(&path).expect("Failed to parse file");
+        assert_eq!(report.config.build.as_deref(), Some("make"));
+        let warnings: Vec<(Option<PathBuf>, String)> = report
+            .warnings
+            .iter()
+            .map(|warning| {
+                let path = warning.location().map(|location| location.path.clone());
+                (path, warning.message())
+            })
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    Some(path.clone()),
+                    String::from("Deprecated key `jdk_11` was migrated, use `jdk11` instead")
+                ),
+                (
+                    Some(temp.join("repo/../shared/base.toml")),
+                    String::from(
+                        "Unknown key `importantRule` is ignored, did you mean `importantRules`?"
+                    )
+                ),
+            ]
+        );
+        assert_eq!(
+            report.warnings[1].location().map(|location| location.line),
+            Some(3)
+        );
+
+        let error = Config::from_file_strict(&path).expect_err("This should have failed");
+        assert!(matches!(
+            error,
+            ConfigError::UnknownKey { ref key, .. } if key == "importantRule"
+        ));
+        Config::from_file_strict(&base).expect_err("This should have failed");
+    }
+
+    #[test]
+    fn it_should_reject_extends_outside_the_top_level() {
+        let temp = TempDir::default();
+        write(&temp, "base.toml", "build = \"make\"\n");
+        let profile = write(
+            &temp,
+            "profile.toml",
+            "[profile.nightly]\nextends = \"base.toml\"\n",
+        );
+        let overrides = write(
+            &temp,
+            "overrides.toml",
+            "[[overrides]]\npaths = [\"web/\"]\n\n[[overrides.overrides]]\npaths = [\"web/legacy/\"]\nextends = \"base.toml\"\n",
+        );
+
+        let error = Config::from_file(&profile).expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "`extends` can only be set at the top level, but {} sets it in `profile.nightly`",
+                profile.display()
+            )
+        );
+        let error = LoadedConfig::from_file(&overrides).expect_err("This should have failed");
+        assert!(matches!(
+            error,
+            ConfigError::NestedExtends { ref table, .. } if table == "overrides.overrides"
+        ));
+
+        let base = write(
+            &temp,
+            "nested.toml",
+            "[profile.ci]\nextends = \"base.toml\"\n",
+        );
+        let path = write(&temp, ".lift.toml", "extends = \"nested.toml\"\n");
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        assert!(matches!(
+            error,
+            ConfigError::ExtendsFailed { ref source, .. }
+                if matches!(**source, ConfigError::NestedExtends { ref path, .. } if *path == base)
+        ));
+    }
+
+    #[test]
+    fn it_should_show_the_chain_that_led_to_a_broken_base() {
+        let temp = TempDir::default();
+        write(&temp, "base.toml", "extends = \"broken.toml\"\n");
+        write(&temp, "broken.toml", "build = \n");
+        let path = write(&temp, ".lift.toml", "extends = \"base.toml\"\n");
+
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        let message = error.to_string();
+        let chain = format!(
+            "{} → {} → {}",
+            path.display(),
+            temp.join("base.toml").display(),
+            temp.join("broken.toml").display()
+        );
+        assert!(
+            message.starts_with(&format!(
+                "Failed to load a configuration through `extends` ({}): Failed to parse file as a toml file at {}:1:",
+                chain,
+                temp.join("broken.toml").display()
+            )),
+            "Unexpected message: {}",
+            message
+        );
+        assert_eq!(error.location().map(|location| location.line), Some(1));
+
+        std::fs::remove_file(temp.join("broken.toml")).expect("Failed to remove broken.toml");
+        let error = Config::from_file(&path).expect_err("This should have failed");
+        assert!(matches!(
+            error,
+            ConfigError::ExtendsFailed { ref source, .. }
+                if matches!(**source, ConfigError::FileNotFound(_))
+        ));
+    }
+}
@@ -2,9 +2,19 @@ use std::path::{Path, PathBuf};
 
 use crate::error::ConfigError;
 
-const CONFIGURATION_FILES: [&str; 5] = [
+const CONFIGURATION_FILES: &[&str] = &[
     ".lift/config.toml",
+    ".lift/config.json",
+    #[cfg(feature = "yaml")]
+    ".lift/config.yaml",
+    #[cfg(feature = "yaml")]
+    ".lift/config.yml",
     ".lift.toml",
+    ".lift.json",
+    #[cfg(feature = "yaml")]
+    ".lift.yaml",
+    #[cfg(feature = "yaml")]
+    ".lift.yml",
     ".muse/config.toml",
     ".muse.toml",
     ".muse/config",
@@ -26,6 +36,58 @@ pub(crate) fn locate_files<P: AsRef<Path>>(path: P) -> Result<Vec<PathBuf>, Conf
     }
 }
 
+/// Finds the `.muse/ignoreFiles` file in the folder, if there is one.
+pub(crate) fn locate_ignores_file<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+    Some(path.as_ref().join(DEFAULT_IGNORES_FILE)).filter(|file| file.exists() && file.is_file())
+}
+
+/// Lists the folders from `path` upwards that contain configuration files, nearest first.
+///
+/// Every folder is searched with [`locate_files`]; a folder with only a `.muse/ignoreFiles` file
+/// is still listed, with no configuration files. The search stops after a folder that contains
+/// `.git` (the repository root), after the `ceiling` folder if it exists, or before it would
+/// cross onto a different filesystem.
+pub(crate) fn locate_ancestor_files<P: AsRef<Path>>(
+    path: P,
+    ceiling: Option<&Path>,
+) -> Result<Vec<(PathBuf, Vec<PathBuf>)>, ConfigError> {
+    let path = path.as_ref();
+    if !(path.exists() && path.is_dir()) {
+        return Err(ConfigError::FolderNotFound(path.to_owned()));
+    }
+    let start = path.canonicalize()?;
+    // A ceiling that does not exist can never be reached, so the search goes on without it.
+    let ceiling = ceiling.and_then(|ceiling| ceiling.canonicalize().ok());
+    let device = device_of(&start)?;
+
+    let mut found = Vec::new();
+    for folder in start.ancestors() {
+        if device_of(folder)? != device {
+            break;
+        }
+        let config_files = locate_files(folder)?;
+        if !config_files.is_empty() || locate_ignores_file(folder).is_some() {
+            found.push((folder.to_path_buf(), config_files));
+        }
+        if folder.join(".git").exists() || Some(folder) == ceiling.as_deref() {
+            break;
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Result<Option<u64>, ConfigError> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok(Some(path.metadata()?.dev()))
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> Result<Option<u64>, ConfigError> {
+    Ok(None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,20 +109,73 @@ mod tests {
         for config in CONFIGURATION_FILES {
             let file_path = temp.join(config);
             if let Some(parent) = file_path.parent() {
-                std::fs::create_dir_all(&parent)
-                    .expect(&format!("Failed to create parent directory: {:?}", parent));
+                std::fs::create_dir_all(parent)
+                    .unwrap_or_else(|_| panic!("Failed to create parent directory: {:?}", parent));
             }
-            File::create(&file_path).expect(&format!("Failed to create {:?}", file_path.clone()));
+            File::create(&file_path).unwrap_or_else(|_| panic!("Failed to create {:?}", file_path));
         }
 
         let found_files = locate_files(&temp).expect("expected temp to be a valid directory");
         let expected: Vec<PathBuf> = vec![
             temp.join(".lift/config.toml"),
+            temp.join(".lift/config.json"),
+            #[cfg(feature = "yaml")]
+            temp.join(".lift/config.yaml"),
+            #[cfg(feature = "yaml")]
+            temp.join(".lift/config.yml"),
             temp.join(".lift.toml"),
+            temp.join(".lift.json"),
+            #[cfg(feature = "yaml")]
+            temp.join(".lift.yaml"),
+            #[cfg(feature = "yaml")]
+            temp.join(".lift.yml"),
             temp.join(".muse/config.toml"),
             temp.join(".muse.toml"),
             temp.join(".muse/config"),
         ];
         assert_eq!(found_files, expected);
     }
+
+    fn create_file(root: &Path, relative: &str) {
+        let file_path = root.join(relative);
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)
+                .unwrap_or_else(|_| panic!("Failed to create parent directory: {:?}", parent));
+        }
+        File::create(&file_path).unwrap_or_else(|_| panic!("Failed to create {:?}", file_path));
+    }
+
+    #[test]
+    fn it_finds_configs_in_ancestors_up_to_the_repository_root() {
+        let temp = TempDir::default();
+        create_file(&temp, "repo/.git/HEAD");
+        create_file(&temp, "repo/.lift.toml");
+        create_file(&temp, "repo/services/.lift.toml");
+        create_file(&temp, "repo/services/api/src/main.rs");
+        create_file(&temp, ".lift.toml");
+
+        let found = locate_ancestor_files(temp.join("repo/services/api"), None)
+            .expect("expected the start to be a valid directory");
+        let repo = temp.canonicalize().unwrap().join("repo");
+        let expected = vec![
+            (
+                repo.join("services"),
+                vec![repo.join("services/.lift.toml")],
+            ),
+            (repo.clone(), vec![repo.join(".lift.toml")]),
+        ];
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn it_stops_searching_ancestors_at_the_ceiling() {
+        let temp = TempDir::default();
+        create_file(&temp, ".lift.toml");
+        create_file(&temp, "services/api/.keep");
+
+        let ceiling = temp.join("services");
+        let found = locate_ancestor_files(temp.join("services/api"), Some(&ceiling))
+            .expect("expected the start to be a valid directory");
+        assert_eq!(found, Vec::new());
+    }
 }
@@ -0,0 +1,358 @@
+use std::{fmt, ops::Range, path::Path};
+
+use serde::{
+    de::{self, DeserializeOwned},
+    Serialize,
+};
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::keys::{
+    find_keys, migrate_deprecated_keys, rename_deprecated_keys, search_key_span,
+    search_nested_key_span, KeyTable,
+};
+use crate::location::Location;
+
+/// A key along with its byte span in the file, if it could be found.
+pub(crate) type KeySpan = (String, Option<Range<usize>>);
+
+/// The file formats that a configuration can be written in.
+///
+/// Formats behind optional features, such as YAML, add variants, so matches on this must have a
+/// wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    Toml,
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    /// Every enabled format, in the order they are tried by [`Format::detect`].
+    pub const ALL: &'static [Format] = &[
+        Format::Toml,
+        Format::Json,
+        #[cfg(feature = "yaml")]
+        Format::Yaml,
+    ];
+
+    /// Picks the format from the file extension, if it is one that is recognized.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("toml") => Some(Format::Toml),
+            Some("json") => Some(Format::Json),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_string<T: Serialize>(self, value: &T) -> Result<String, ConfigError> {
+        match self {
+            Format::Toml => toml::to_string(value).map_err(ConfigError::TomlSerializeFailed),
+            Format::Json => {
+                serde_json::to_string_pretty(value).map_err(ConfigError::JsonSerializeFailed)
+            }
+            #[cfg(feature = "yaml")]
+            Format::Yaml => serde_yaml::to_string(value).map_err(ConfigError::YamlSerializeFailed),
+        }
+    }
+
+    /// Lists the keys of the top-level table, with their byte spans where they can be found.
+    pub(crate) fn top_level_keys(
+        self,
+        path: &Path,
+        contents: &str,
+    ) -> Result<Vec<KeySpan>, ConfigError> {
+        match self {
+            Format::Toml => {
+                let document = toml_edit::ImDocument::parse(contents)
+                    .map_err(|e| toml_edit_error(path, contents, e))?;
+                let table = document.as_table();
+                Ok(table
+                    .iter()
+                    .map(|(key, _item)| {
+                        let span = table.key(key).and_then(toml_edit::Key::span);
+                        (String::from(key), span)
+                    })
+                    .collect())
+            }
+            Format::Json => {
+                let table: serde_json::Map<String, serde_json::Value> =
+                    serde_json::from_str(contents).map_err(|e| json_error(path, contents, e))?;
+                Ok(table
+                    .into_iter()
+                    .map(|(key, _value)| {
+                        let span = search_key_span(contents, &key, true);
+                        (key, span)
+                    })
+                    .collect())
+            }
+            #[cfg(feature = "yaml")]
+            Format::Yaml => {
+                let table: serde_yaml::Mapping =
+                    serde_yaml::from_str(contents).map_err(|e| yaml_error(path, contents, e))?;
+                Ok(table
+                    .into_iter()
+                    .filter_map(|(key, _value)| key.as_str().map(String::from))
+                    .map(|key| {
+                        let span = search_key_span(contents, &key, false);
+                        (key, span)
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Lists the keys of each `[profile.<name>]` table and of each entry of `[[overrides]]`,
+    /// along with the table they are in, with their byte spans where they can be found.
+    pub(crate) fn nested_keys(
+        self,
+        path: &Path,
+        contents: &str,
+    ) -> Result<Vec<(KeyTable, KeySpan)>, ConfigError> {
+        if let Format::Toml = self {
+            let document = toml_edit::ImDocument::parse(contents)
+                .map_err(|e| toml_edit_error(path, contents, e))?;
+            let mut keys = Vec::new();
+            if let Some(profiles) = document
+                .get("profile")
+                .and_then(|item| item.as_table_like())
+            {
+                for (name, item) in profiles.iter() {
+                    if let Some(table) = item.as_table_like() {
+                        keys.extend(
+                            toml_table_keys(table)
+                                .map(|key_span| (KeyTable::Profile(String::from(name)), key_span)),
+                        );
+                    }
+                }
+            }
+            let overrides: Vec<&dyn toml_edit::TableLike> = match document.get("overrides") {
+                Some(toml_edit::Item::ArrayOfTables(entries)) => entries
+                    .iter()
+                    .map(|table| table as &dyn toml_edit::TableLike)
+                    .collect(),
+                Some(toml_edit::Item::Value(toml_edit::Value::Array(entries))) => entries
+                    .iter()
+                    .filter_map(|entry| entry.as_inline_table())
+                    .map(|table| table as &dyn toml_edit::TableLike)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for (index, table) in overrides.into_iter().enumerate() {
+                keys.extend(
+                    toml_table_keys(table).map(|key_span| (KeyTable::Override(index), key_span)),
+                );
+            }
+            return Ok(keys);
+        }
+        // The other formats do not report spans, so each key is searched for after the start of
+        // its table: the name of its profile, which is itself searched for after the `profile`
+        // key, or the end of the previous override, searched for after the `overrides` key.
+        let quoted = self == Format::Json;
+        let raw: serde_json::Value = self.parse(path, contents)?;
+        let mut keys = Vec::new();
+        if let Some(profiles) = raw.get("profile").and_then(serde_json::Value::as_object) {
+            let profile_start = search_key_span(contents, "profile", quoted).map(|span| span.end);
+            for (name, profile) in profiles {
+                let start = profile_start
+                    .and_then(|start| search_nested_key_span(contents, start, name, quoted))
+                    .map(|span| span.end);
+                for key in profile
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|table| table.keys())
+                {
+                    let span = start
+                        .and_then(|start| search_nested_key_span(contents, start, key, quoted));
+                    keys.push((KeyTable::Profile(name.clone()), (key.clone(), span)));
+                }
+            }
+        }
+        if let Some(overrides) = raw.get("overrides").and_then(serde_json::Value::as_array) {
+            let mut start = search_key_span(contents, "overrides", quoted).map(|span| span.end);
+            for (index, entry) in overrides.iter().enumerate() {
+                let entry_start = start;
+                for key in entry.as_object().into_iter().flat_map(|table| table.keys()) {
+                    let span = entry_start
+                        .and_then(|start| search_nested_key_span(contents, start, key, quoted));
+                    if let Some(span) = &span {
+                        start = start.max(Some(span.end));
+                    }
+                    keys.push((KeyTable::Override(index), (key.clone(), span)));
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Tries each of [`Format::ALL`] in turn, returning the first format that parses the contents.
+    ///
+    /// If none of them do, the error collects the parse error from every attempt.
+    pub(crate) fn detect<T: DeserializeOwned>(
+        path: &Path,
+        contents: &str,
+    ) -> Result<(Self, T), ConfigError> {
+        let mut attempts = Vec::new();
+        for format in Format::ALL {
+            match format.parse(path, contents) {
+                Ok(parsed) => return Ok((*format, parsed)),
+                Err(e) => attempts.push((*format, e)),
+            }
+        }
+        Err(ConfigError::FormatDetectionFailed(attempts))
+    }
+
+    /// Parses the contents of the file at `path`, which is only used to report where errors are.
+    pub(crate) fn parse<T: DeserializeOwned>(
+        self,
+        path: &Path,
+        contents: &str,
+    ) -> Result<T, ConfigError> {
+        match self {
+            Format::Toml => toml::from_str(contents).map_err(|e| toml_error(path, contents, e)),
+            Format::Json => {
+                serde_json::from_str(contents).map_err(|e| json_error(path, contents, e))
+            }
+            #[cfg(feature = "yaml")]
+            Format::Yaml => {
+                serde_yaml::from_str(contents).map_err(|e| yaml_error(path, contents, e))
+            }
+        }
+    }
+
+    /// Parses a configuration, first migrating any deprecated keys to their modern names with
+    /// [`migrate_deprecated_keys`].
+    pub(crate) fn parse_config(self, path: &Path, contents: &str) -> Result<Config, ConfigError> {
+        let mut raw: serde_json::Value = self.parse(path, contents)?;
+        if !migrate_deprecated_keys(&mut raw) {
+            // Parsing the text rather than the raw table keeps the location of invalid values.
+            return self.parse(path, contents);
+        }
+        // The keys are renamed without moving anything else, so errors in the renamed text are
+        // located in the original one.
+        let keys = find_keys(path, contents, self)?;
+        if let Some(renamed) = rename_deprecated_keys(contents, &keys) {
+            return match self {
+                Format::Toml => toml::from_str(&renamed).map_err(|e| toml_error(path, contents, e)),
+                Format::Json => {
+                    serde_json::from_str(&renamed).map_err(|e| json_error(path, contents, e))
+                }
+                #[cfg(feature = "yaml")]
+                Format::Yaml => {
+                    serde_yaml::from_str(&renamed).map_err(|e| yaml_error(path, contents, e))
+                }
+            };
+        }
+        serde_json::from_value(raw).map_err(|e| match self {
+            Format::Toml => toml_error(path, contents, de::Error::custom(e)),
+            Format::Json => json_error(path, contents, e),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => yaml_error(path, contents, de::Error::custom(e)),
+        })
+    }
+}
+
+fn toml_table_keys(table: &dyn toml_edit::TableLike) -> impl Iterator<Item = KeySpan> + '_ {
+    table.iter().map(move |(key, _item)| {
+        let span = table.key(key).and_then(toml_edit::Key::span);
+        (String::from(key), span)
+    })
+}
+
+fn toml_error(path: &Path, contents: &str, e: toml::de::Error) -> ConfigError {
+    ConfigError::FileTomlParseFailed {
+        path: path.to_path_buf(),
+        location: e
+            .span()
+            .map(|span| Location::from_span(path, contents, span)),
+        source: Box::new(e),
+    }
+}
+
+pub(crate) fn toml_edit_error(path: &Path, contents: &str, e: toml_edit::TomlError) -> ConfigError {
+    ConfigError::FileTomlEditFailed {
+        path: path.to_path_buf(),
+        location: e
+            .span()
+            .map(|span| Location::from_span(path, contents, span)),
+        source: Box::new(e),
+    }
+}
+
+fn json_error(path: &Path, contents: &str, e: serde_json::Error) -> ConfigError {
+    // serde_json reports line 0 for errors that are not about the input's syntax.
+    let location =
+        (e.line() > 0).then(|| Location::from_line_column(path, contents, e.line(), e.column()));
+    ConfigError::FileJsonParseFailed {
+        path: path.to_path_buf(),
+        location,
+        source: e,
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_error(path: &Path, contents: &str, e: serde_yaml::Error) -> ConfigError {
+    ConfigError::FileYamlParseFailed {
+        path: path.to_path_buf(),
+        location: e.location().map(|location| {
+            Location::from_span(path, contents, location.index()..location.index())
+        }),
+        source: e,
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Toml => write!(f, "toml"),
+            Format::Json => write!(f, "json"),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => write!(f, "yaml"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_picks_the_format_from_the_extension() {
+        assert_eq!(Format::from_path(".lift.toml"), Some(Format::Toml));
+        assert_eq!(Format::from_path(".lift/config.json"), Some(Format::Json));
+        assert_eq!(Format::from_path(".muse/config"), None);
+    }
+
+    #[test]
+    fn it_detects_json_content() {
+        let (format, value): (Format, serde_json::Value) =
+            Format::detect(Path::new(".lift"), r#"{"setup": "make"}"#)
+                .expect("Failed to detect format");
+        assert_eq!(format, Format::Json);
+        assert_eq!(value, serde_json::json!({"setup": "make"}));
+    }
+
+    #[test]
+    fn it_collects_every_error_when_detection_fails() {
+        let error = Format::detect::<serde_json::Value>(Path::new(".lift"), r#"{"setup": ["#)
+            .expect_err("This should have failed");
+        match error {
+            ConfigError::FormatDetectionFailed(attempts) => {
+                let formats: Vec<Format> = attempts.iter().map(|(format, _e)| *format).collect();
+                assert_eq!(formats, Format::ALL);
+            }
+            e => panic!("Expected a FormatDetectionFailed, but got {:?}", e),
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn it_picks_yaml_from_either_extension() {
+        assert_eq!(Format::from_path(".lift.yaml"), Some(Format::Yaml));
+        assert_eq!(Format::from_path(".lift/config.yml"), Some(Format::Yaml));
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::{Component, Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::error::ConfigError;
+
+/// A compiled `ignoreFiles` list that matches paths with the same semantics as a `.gitignore`.
+///
+/// Patterns are resolved relative to the repository root, which is the folder that holds the
+/// configuration, and the paths being checked must be relative to that same root:
+///
+/// * a pattern with a `/` at the start or in the middle (`/build`, `src/test/`) is anchored to the
+///   root, while a pattern without one (`*.log`) matches at any depth
+/// * a pattern ending in `/` only matches directories, and so everything beneath them
+/// * `**` matches any number of directories (`**/generated/**`)
+/// * a pattern starting with `!` re-includes paths excluded by an earlier pattern
+///
+/// When several patterns match, the last one wins. As in git, a path cannot be re-included once
+/// one of its parent directories is excluded, so `!src/test/fixtures/` has no effect after
+/// `src/test/`; write `src/test/*` to exclude the directory's contents but not the directory.
+#[derive(Debug, Clone)]
+pub struct IgnoreMatcher {
+    gitignore: Gitignore,
+}
+
+impl IgnoreMatcher {
+    /// Compiles newline separated patterns, failing on the first one that is not a valid glob.
+    pub fn new(patterns: &str) -> Result<Self, ConfigError> {
+        IgnoreMatcher::build(patterns, true)
+    }
+
+    /// Compiles newline separated patterns, skipping any that are not valid globs like git does.
+    pub(crate) fn new_lenient(patterns: &str) -> Self {
+        IgnoreMatcher::build(patterns, false)
+            .expect("invalid ignore patterns should have been skipped")
+    }
+
+    fn build(patterns: &str, strict: bool) -> Result<Self, ConfigError> {
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns.lines() {
+            match builder.add_line(None, pattern) {
+                Err(e) if strict => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(IgnoreMatcher {
+            gitignore: builder.build()?,
+        })
+    }
+
+    /// Checks whether a file, given relative to the repository root, is ignored either directly
+    /// or because one of its parent directories is.
+    pub fn is_ignored<P: AsRef<Path>>(&self, relative_path: P) -> bool {
+        self.is_match(relative_path.as_ref(), false)
+    }
+
+    /// Checks whether a directory, given relative to the repository root, is ignored either
+    /// directly or because one of its parent directories is.
+    pub fn is_dir_ignored<P: AsRef<Path>>(&self, relative_path: P) -> bool {
+        self.is_match(relative_path.as_ref(), true)
+    }
+
+    /// Checks each parent directory from the root down before the path itself, as git stops
+    /// descending into a directory as soon as it is excluded.
+    fn is_match(&self, relative_path: &Path, is_dir: bool) -> bool {
+        let path = normalize(relative_path);
+        let mut parent = PathBuf::new();
+        if let Some((_name, parents)) = path.components().collect::<Vec<_>>().split_last() {
+            for component in parents {
+                parent.push(component);
+                if self.gitignore.matched(&parent, true).is_ignore() {
+                    return true;
+                }
+            }
+        }
+        self.gitignore.matched(&path, is_dir).is_ignore()
+    }
+}
+
+/// Rewrites gitignore-style patterns written relative to `folder` so that they match the same
+/// paths relative to the root `folder` is in.
+///
+/// Anchored patterns (`/build`, `src/test/`) are prefixed with the folder, and patterns that match
+/// at any depth (`*.log`) are limited to the folder with `**/`. Blank lines, comments and the `!`
+/// of negated patterns are kept.
+pub(crate) fn reroot_patterns(patterns: &str, folder: &Path) -> String {
+    let folder = normalize(folder);
+    if folder.as_os_str().is_empty() {
+        return String::from(patterns);
+    }
+    let prefix: String = folder
+        .components()
+        .map(|component| escape_glob(&component.as_os_str().to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join("/");
+    patterns
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() || line.starts_with('#') {
+                return String::from(line);
+            }
+            let (negation, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => ("!", pattern),
+                None => ("", line),
+            };
+            let anchored = pattern.trim_end_matches('/').contains('/');
+            match pattern.strip_prefix('/') {
+                Some(pattern) => format!("{}/{}/{}", negation, prefix, pattern),
+                None if anchored => format!("{}/{}/{}", negation, prefix, pattern),
+                None => format!("{}/{}/**/{}", negation, prefix, pattern),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escapes the characters that have a meaning in globs, so a folder name matches literally.
+fn escape_glob(name: &str) -> String {
+    name.chars()
+        .flat_map(|c| match c {
+            '*' | '?' | '[' | ']' | '\\' | '{' | '}' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect()
+}
+
+/// Drops any leading `/` or `./` so that paths written either way are relative to the root.
+pub(crate) fn normalize(relative_path: &Path) -> PathBuf {
+    relative_path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_) | Component::ParentDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(patterns: &str) -> IgnoreMatcher {
+        IgnoreMatcher::new(patterns).expect("Failed to compile patterns")
+    }
+
+    #[test]
+    fn it_matches_unanchored_patterns_at_any_depth() {
+        let matcher = matcher("*.log\nrequirements.txt");
+        assert!(matcher.is_ignored("debug.log"));
+        assert!(matcher.is_ignored("services/api/debug.log"));
+        assert!(matcher.is_ignored("python/requirements.txt"));
+        assert!(!matcher.is_ignored("src/main.rs"));
+    }
+
+    #[test]
+    fn it_anchors_patterns_containing_a_slash() {
+        let matcher = matcher("/build\nsrc/test/");
+        assert!(matcher.is_ignored("build/output.jar"));
+        assert!(!matcher.is_ignored("services/build/output.jar"));
+        assert!(matcher.is_ignored("src/test/MainTest.java"));
+        assert!(!matcher.is_ignored("services/src/test/MainTest.java"));
+    }
+
+    #[test]
+    fn it_matches_directory_only_patterns_against_directories() {
+        let matcher = matcher("generated/");
+        assert!(matcher.is_ignored("generated/Model.java"));
+        assert!(matcher.is_dir_ignored("generated"));
+        assert!(!matcher.is_ignored("generated"));
+    }
+
+    #[test]
+    fn it_matches_double_stars_across_directories() {
+        let matcher = matcher("**/fixtures/**/*.json");
+        assert!(matcher.is_ignored("fixtures/data.json"));
+        assert!(matcher.is_ignored("web/test/fixtures/users/admin.json"));
+        assert!(!matcher.is_ignored("web/test/fixtures/users/admin.js"));
+    }
+
+    #[test]
+    fn it_re_includes_negated_patterns() {
+        let matcher = matcher("*.json\n!package.json");
+        assert!(matcher.is_ignored("data.json"));
+        assert!(!matcher.is_ignored("web/package.json"));
+    }
+
+    #[test]
+    fn it_does_not_re_include_paths_under_an_excluded_directory() {
+        let excluded = matcher("src/test/\n!src/test/fixtures/\n!src/test/Keep.java");
+        assert!(excluded.is_ignored("src/test/fixtures/data.json"));
+        assert!(excluded.is_ignored("src/test/Keep.java"));
+
+        let contents_excluded = matcher("src/test/*\n!src/test/fixtures/");
+        assert!(contents_excluded.is_ignored("src/test/MainTest.java"));
+        assert!(!contents_excluded.is_ignored("src/test/fixtures/data.json"));
+        assert!(!contents_excluded.is_dir_ignored("src/test/fixtures"));
+    }
+
+    #[test]
+    fn it_reroots_patterns_to_a_nested_folder() {
+        let patterns = "/generated/\nsrc/test/\n*.log\n\n# comment\n!keep.log";
+        let rerooted = reroot_patterns(patterns, Path::new("services/payments"));
+        assert_eq!(
+            rerooted,
+            "/services/payments/generated/\n/services/payments/src/test/\n\
+             /services/payments/**/*.log\n\n# comment\n!/services/payments/**/keep.log"
+        );
+        let rerooted = matcher(&rerooted);
+        assert!(rerooted.is_ignored("services/payments/generated/X.java"));
+        assert!(rerooted.is_ignored("services/payments/src/test/A.java"));
+        assert!(rerooted.is_ignored("services/payments/deep/debug.log"));
+        assert!(!rerooted.is_ignored("services/payments/keep.log"));
+        assert!(!rerooted.is_ignored("generated/X.java"));
+        assert!(!rerooted.is_ignored("debug.log"));
+        assert_eq!(reroot_patterns(patterns, Path::new("")), patterns);
+    }
+
+    #[test]
+    fn it_accepts_paths_written_from_the_root() {
+        let matcher = matcher("/build");
+        assert!(matcher.is_ignored("/build/output.jar"));
+        assert!(matcher.is_ignored("./build/output.jar"));
+    }
+
+    #[test]
+    fn it_rejects_invalid_patterns_unless_lenient() {
+        IgnoreMatcher::new("build/\nsrc/{test").expect_err("This should have failed");
+        let matcher = IgnoreMatcher::new_lenient("build/\nsrc/{test");
+        assert!(matcher.is_ignored("build/output.jar"));
+    }
+}
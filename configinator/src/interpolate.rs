@@ -0,0 +1,160 @@
+use crate::error::ConfigError;
+
+/// Substitutes the variables in a value of `key`.
+///
+/// `${NAME}` is replaced with the value of `NAME`, and is an error if `NAME` is not defined.
+/// `${NAME:-default}` is replaced with `default` instead when `NAME` is undefined or empty; the
+/// default is used as it is, and may not contain another `${`, as nested variables are not
+/// supported. `$$` is a literal `$`, so `$${NAME}` leaves `${NAME}` for the shell. A `$` followed
+/// by anything else, as in `$HOME` or `$1`, is also left as it is.
+///
+/// Names must start with a letter or `_` and contain only letters, digits and `_`.
+pub(crate) fn interpolate<F>(key: &str, value: &str, lookup: F) -> Result<String, ConfigError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let invalid = |reason: String| ConfigError::InvalidInterpolation {
+        key: String::from(key),
+        reason,
+    };
+    let mut interpolated = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('$') {
+        interpolated.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            interpolated.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| invalid(format!("`{}` is missing its closing `}}`", rest)))?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            if default.is_some_and(|default| default.contains("${")) {
+                return Err(invalid(format!(
+                    "the default of `{}` contains another variable, which is not supported",
+                    name
+                )));
+            }
+            if !is_name(name) {
+                return Err(invalid(format!(
+                    "`{}` is not a valid variable name",
+                    &after[..end]
+                )));
+            }
+            match (lookup(name), default) {
+                (Some(found), Some(default)) if found.is_empty() => interpolated.push_str(default),
+                (Some(found), _) => interpolated.push_str(&found),
+                (None, Some(default)) => interpolated.push_str(default),
+                (None, None) => {
+                    return Err(ConfigError::UndefinedVariable {
+                        key: String::from(key),
+                        name: String::from(name),
+                    })
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            interpolated.push('$');
+            rest = &rest[1..];
+        }
+    }
+    interpolated.push_str(rest);
+    Ok(interpolated)
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "PROJECT_DIR" => Some(String::from("app")),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn it_substitutes_variables() {
+        assert_eq!(
+            interpolate("build", "gradlew -p ${PROJECT_DIR} assemble", lookup)
+                .expect("Failed to interpolate"),
+            "gradlew -p app assemble"
+        );
+        assert_eq!(
+            interpolate("build", "${PROJECT_DIR}${PROJECT_DIR}", lookup)
+                .expect("Failed to interpolate"),
+            "appapp"
+        );
+    }
+
+    #[test]
+    fn it_uses_defaults_for_undefined_or_empty_variables() {
+        assert_eq!(
+            interpolate("setup", "${LIFT_ROOT:-.}/deps.sh", lookup).expect("Failed to interpolate"),
+            "./deps.sh"
+        );
+        assert_eq!(
+            interpolate("setup", "${EMPTY:-none}", lookup).expect("Failed to interpolate"),
+            "none"
+        );
+        assert_eq!(
+            interpolate("setup", "${PROJECT_DIR:-none}", lookup).expect("Failed to interpolate"),
+            "app"
+        );
+        assert_eq!(
+            interpolate("setup", "${EMPTY}", lookup).expect("Failed to interpolate"),
+            ""
+        );
+    }
+
+    #[test]
+    fn it_leaves_escaped_and_shell_variables_alone() {
+        assert_eq!(
+            interpolate("build", "echo $${HOME} $HOME $1 $$ $", lookup)
+                .expect("Failed to interpolate"),
+            "echo ${HOME} $HOME $1 $ $"
+        );
+    }
+
+    #[test]
+    fn it_reports_undefined_variables() {
+        let error = interpolate("build", "make -C ${PROJECT}", lookup)
+            .expect_err("This should have failed");
+        assert_eq!(error.to_string(), "Undefined variable `PROJECT` in `build`");
+    }
+
+    #[test]
+    fn it_reports_malformed_variables() {
+        let error = interpolate("build", "make ${PROJECT_DIR", lookup)
+            .expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            "Invalid interpolation in `build`: `${PROJECT_DIR` is missing its closing `}`"
+        );
+        let error =
+            interpolate("build", "make ${1DIR}", lookup).expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            "Invalid interpolation in `build`: `1DIR` is not a valid variable name"
+        );
+        let error = interpolate("build", "make ${PROJECT:-${PROJECT_DIR}}", lookup)
+            .expect_err("This should have failed");
+        assert_eq!(
+            error.to_string(),
+            "Invalid interpolation in `build`: the default of `PROJECT` contains another variable, which is not supported"
+        );
+    }
+}
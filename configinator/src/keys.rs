@@ -0,0 +1,312 @@
+use std::{ops::Range, path::Path};
+
+use crate::error::ConfigError;
+use crate::format::Format;
+use crate::location::Location;
+
+/// Every top-level key that [`Config`](crate::Config) accepts, as written in configuration files.
+pub(crate) const KEYS: &[&str] = &[
+    "setup",
+    "build",
+    "importantRules",
+    "ignoreRules",
+    "ignoreFiles",
+    "tools",
+    "disableTools",
+    "customTools",
+    "allow",
+    "jdk11",
+    "androidVersion",
+    "errorproneBugPatterns",
+    "summaryComments",
+    "overrides",
+    "profile",
+    "extends",
+];
+
+/// Keys from older versions of the schema, along with the key that replaced them.
+pub(crate) const DEPRECATED_KEYS: &[(&str, &str)] = &[
+    ("important_rules", "importantRules"),
+    ("ignore_rules", "ignoreRules"),
+    ("ignore_files", "ignoreFiles"),
+    ("disable_tools", "disableTools"),
+    ("custom_tools", "customTools"),
+    ("jdk_11", "jdk11"),
+    ("android_version", "androidVersion"),
+    ("errorprone_bug_patterns", "errorproneBugPatterns"),
+    ("summary_comments", "summaryComments"),
+];
+
+/// The modern name of a key, which is the key itself unless it is one of [`DEPRECATED_KEYS`].
+pub(crate) fn modern_key(key: &str) -> &str {
+    DEPRECATED_KEYS
+        .iter()
+        .find(|(deprecated, _replacement)| *deprecated == key)
+        .map_or(key, |(_deprecated, replacement)| replacement)
+}
+
+/// Renames the deprecated keys of a raw configuration table, and of the tables in its
+/// `overrides` and `profile`, to their modern names. A deprecated key whose modern name is also
+/// set is dropped instead, so the modern key wins.
+///
+/// Returns whether any key was renamed or dropped.
+pub(crate) fn migrate_deprecated_keys(raw: &mut serde_json::Value) -> bool {
+    let table = match raw.as_object_mut() {
+        Some(table) => table,
+        None => return false,
+    };
+    let mut migrated = false;
+    for (deprecated, replacement) in DEPRECATED_KEYS {
+        if let Some(value) = table.remove(*deprecated) {
+            migrated = true;
+            if !table.contains_key(*replacement) {
+                table.insert(String::from(*replacement), value);
+            }
+        }
+    }
+    if let Some(overrides) = table
+        .get_mut("overrides")
+        .and_then(serde_json::Value::as_array_mut)
+    {
+        for entry in overrides {
+            migrated |= migrate_deprecated_keys(entry);
+        }
+    }
+    if let Some(profiles) = table
+        .get_mut("profile")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        for profile in profiles.values_mut() {
+            migrated |= migrate_deprecated_keys(profile);
+        }
+    }
+    migrated
+}
+
+/// Renames the deprecated keys in the text of a configuration file to their modern names, like
+/// [`migrate_deprecated_keys`], so that it can be parsed with the locations of its values intact.
+///
+/// Each modern name is padded with spaces to the length of the deprecated one, so every other
+/// byte stays where it was. A deprecated key whose modern name is also set in the same table is
+/// left as it is, as an unknown key it is then ignored. Returns `None` if any deprecated key
+/// could not be found in the text.
+pub(crate) fn rename_deprecated_keys(contents: &str, keys: &[KeyOccurrence]) -> Option<String> {
+    let mut renamed = String::from(contents);
+    for occurrence in keys {
+        let replacement = modern_key(&occurrence.key);
+        let superseded = keys
+            .iter()
+            .any(|other| other.table == occurrence.table && other.key == replacement);
+        if replacement == occurrence.key || superseded {
+            continue;
+        }
+        let span = occurrence.location.as_ref()?.span.clone();
+        let written = contents.get(span.clone())?;
+        let replacement = match written.chars().next() {
+            Some(quote @ ('"' | '\'')) if written.len() > occurrence.key.len() => {
+                format!("{}{}{}", quote, replacement, quote)
+            }
+            _ => String::from(replacement),
+        };
+        let padding = written.len().checked_sub(replacement.len())?;
+        renamed.replace_range(span, &format!("{}{}", replacement, " ".repeat(padding)));
+    }
+    Some(renamed)
+}
+
+/// The table of a configuration file that a key is in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum KeyTable {
+    TopLevel,
+
+    /// A `[profile.<name>]` table, with the name of the profile.
+    Profile(String),
+
+    /// An entry of `[[overrides]]`, with its index.
+    Override(usize),
+}
+
+/// A key of a configuration file, as it appears in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KeyOccurrence {
+    pub(crate) key: String,
+    pub(crate) location: Option<Location>,
+    pub(crate) table: KeyTable,
+}
+
+/// Lists the top-level keys of a configuration file, along with the keys of each of its
+/// profiles and overrides, in the order they appear.
+pub(crate) fn find_keys(
+    path: &Path,
+    contents: &str,
+    format: Format,
+) -> Result<Vec<KeyOccurrence>, ConfigError> {
+    let top_level_keys = format
+        .top_level_keys(path, contents)?
+        .into_iter()
+        .map(|key_span| (KeyTable::TopLevel, key_span));
+    let nested_keys = format.nested_keys(path, contents)?;
+    let mut keys: Vec<KeyOccurrence> = top_level_keys
+        .chain(nested_keys)
+        .map(|(table, (key, span))| KeyOccurrence {
+            location: span.map(|span| Location::from_span(path, contents, span)),
+            key,
+            table,
+        })
+        .collect();
+    keys.sort_by_key(|occurrence| {
+        occurrence
+            .location
+            .as_ref()
+            .map_or(usize::MAX, |location| location.span.start)
+    });
+    Ok(keys)
+}
+
+/// Finds the known key closest to an unknown one by edit distance, ignoring case, if any is
+/// close enough to plausibly be what was meant.
+pub(crate) fn suggest(key: &str) -> Option<&'static str> {
+    let lowercase = key.to_lowercase();
+    KEYS.iter()
+        .map(|known| {
+            (
+                *known,
+                strsim::levenshtein(&lowercase, &known.to_lowercase()),
+            )
+        })
+        .filter(|(known, distance)| *distance <= (known.len() / 3).max(1))
+        .min_by_key(|(_known, distance)| *distance)
+        .map(|(known, _distance)| known)
+}
+
+/// Finds the span of a top-level key in text formats that do not report spans themselves, by
+/// looking for the key followed by a `:`. `quoted` looks for `"key"`, as in JSON, directly inside
+/// the outermost object; otherwise the key must start a line without any indentation, as
+/// top-level keys do in YAML.
+pub(crate) fn search_key_span(contents: &str, key: &str, quoted: bool) -> Option<Range<usize>> {
+    let mut start = 0;
+    while let Some(span) = search_key_span_from(contents, start, key, quoted, false) {
+        if !quoted || json_depth(&contents[..span.start]) == 1 {
+            return Some(span);
+        }
+        start = span.end;
+    }
+    None
+}
+
+/// How many objects and arrays are open at the end of `prefix`, the start of a JSON document.
+fn json_depth(prefix: &str) -> usize {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in prefix.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' | '[' if !in_string => depth += 1,
+            '}' | ']' if !in_string => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// Finds the span of a key in a nested table like [`search_key_span`], looking only from byte
+/// `start`, where the table begins. An unquoted key may be indented, as nested keys are in YAML.
+pub(crate) fn search_nested_key_span(
+    contents: &str,
+    start: usize,
+    key: &str,
+    quoted: bool,
+) -> Option<Range<usize>> {
+    search_key_span_from(contents, start, key, quoted, true)
+}
+
+fn search_key_span_from(
+    contents: &str,
+    start: usize,
+    key: &str,
+    quoted: bool,
+    indented: bool,
+) -> Option<Range<usize>> {
+    let needle = if quoted {
+        format!("\"{}\"", key)
+    } else {
+        String::from(key)
+    };
+    contents
+        .get(start..)?
+        .match_indices(&needle)
+        .map(|(index, _)| start + index..start + index + needle.len())
+        .find(|span| {
+            let line_start = contents[..span.start]
+                .rfind('\n')
+                .map_or(0, |index| index + 1);
+            let indent = &contents[line_start..span.start];
+            // A nested key may also follow the `-` of a YAML list entry.
+            let starts_line = indent.is_empty()
+                || (indented && indent.chars().all(|c| c.is_whitespace() || c == '-'));
+            (quoted || starts_line) && contents[span.end..].trim_start().starts_with(':')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_suggests_the_closest_key() {
+        assert_eq!(suggest("importantRule"), Some("importantRules"));
+        assert_eq!(suggest("ignorefiles"), Some("ignoreFiles"));
+        assert_eq!(suggest("disabledTools"), Some("disableTools"));
+        assert_eq!(suggest("jdk"), None);
+        assert_eq!(suggest("alow"), Some("allow"));
+        assert_eq!(suggest("somethingElse"), None);
+    }
+
+    #[test]
+    fn it_migrates_deprecated_keys_at_every_level() {
+        let mut raw = serde_json::json!({
+            "important_rules": ["A"],
+            "ignoreRules": ["B"],
+            "ignore_rules": ["C"],
+            "overrides": [{"paths": ["web/"], "jdk_11": true}],
+            "profile": {"nightly": {"summary_comments": false}}
+        });
+        assert!(migrate_deprecated_keys(&mut raw));
+        assert_eq!(
+            raw,
+            serde_json::json!({
+                "importantRules": ["A"],
+                "ignoreRules": ["B"],
+                "overrides": [{"paths": ["web/"], "jdk11": true}],
+                "profile": {"nightly": {"summaryComments": false}}
+            })
+        );
+        assert!(!migrate_deprecated_keys(&mut raw));
+    }
+
+    #[test]
+    fn it_searches_for_json_and_yaml_keys() {
+        let json = r#"{"setup": "tools", "tools" : []}"#;
+        assert_eq!(search_key_span(json, "tools", true), Some(19..26));
+        let yaml = "setup: tools\ntools:\n  - infer\n";
+        assert_eq!(search_key_span(yaml, "tools", false), Some(13..18));
+        let json = r#"{"profile": {"ci": {"build": "x"}}, "b\"{": 1, "build": "y"}"#;
+        assert_eq!(search_key_span(json, "build", true), Some(47..54));
+        let yaml = "profile:\n  ci:\n    build: gradle\nbuild: make\n";
+        assert_eq!(search_key_span(yaml, "build", false), Some(33..38));
+        let nested = "build: make\nprofile:\n  ci:\n    build: gradle\n";
+        assert_eq!(search_key_span(nested, "build", false), Some(0..5));
+        assert_eq!(
+            search_nested_key_span(nested, 21, "build", false),
+            Some(31..36)
+        );
+        let overrides = "overrides:\n  - paths: [web/]\n    tools: []\n";
+        assert_eq!(
+            search_nested_key_span(overrides, 10, "paths", false),
+            Some(15..20)
+        );
+    }
+}
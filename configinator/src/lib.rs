@@ -1,5 +1,37 @@
 mod config;
+mod diff;
+mod document;
+mod env;
 
-pub use config::Config;
+pub use config::{AncestorConfig, Config};
+pub use diff::{Change, ConfigDiff, FieldChange};
+pub use document::ConfigDocument;
+pub use env::DEFAULT_ENV_PREFIX;
 pub mod error;
+mod extends;
 mod filesystem;
+mod format;
+mod ignore_files;
+mod interpolate;
+mod keys;
+mod loaded;
+mod location;
+mod overrides;
+mod report;
+mod rules;
+#[cfg(feature = "schema")]
+mod schema;
+mod tools;
+mod tree;
+mod validate;
+
+pub use format::Format;
+pub use ignore_files::IgnoreMatcher;
+pub use loaded::{LoadedConfig, Origin};
+pub use location::Location;
+pub use overrides::Override;
+pub use report::{LoadReport, LoadWarning};
+pub use rules::RuleDisposition;
+pub use tools::{Tool, ToolConflict, ToolRegistry, ToolSelection, ToolSource};
+pub use tree::{ConfigTree, FailedFolder, ResolvedConfig};
+pub use validate::{Diagnostic, DiagnosticCode, Severity, ANDROID_VERSIONS};
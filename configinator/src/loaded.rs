@@ -0,0 +1,527 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::config::{parse, read_file, trim_lines, Config, CONCATENATED_KEYS};
+use crate::env::variable_for;
+use crate::error::ConfigError;
+use crate::extends::with_bases;
+use crate::filesystem::{locate_files, locate_ignores_file};
+use crate::format::Format;
+use crate::keys::{find_keys, modern_key, KeyOccurrence, KeyTable};
+use crate::location::Location;
+use crate::report::{collect_warnings, LoadWarning};
+use crate::validate::Diagnostic;
+
+/// Where the value of a key came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// A key in a configuration file. The location is missing if the key could not be found in
+    /// the text of the file.
+    File {
+        path: PathBuf,
+        location: Option<Location>,
+    },
+
+    /// The lines of a `.muse/ignoreFiles` file, which only contribute to `ignoreFiles`.
+    IgnoresFile { path: PathBuf },
+
+    /// An environment variable, as read by [`LoadedConfig::from_env`].
+    Environment { variable: String },
+}
+
+impl Origin {
+    /// The file the value came from, if it came from one.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Origin::File { path, .. } | Origin::IgnoresFile { path } => Some(path),
+            Origin::Environment { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File {
+                location: Some(location),
+                ..
+            } => write!(f, "{}", location),
+            Origin::File {
+                path,
+                location: None,
+            } => write!(f, "{}", path.display()),
+            Origin::IgnoresFile { path } => write!(f, "ignores file {}", path.display()),
+            Origin::Environment { variable } => write!(f, "environment variable {}", variable),
+        }
+    }
+}
+
+/// A configuration along with where the value of each of its keys came from.
+///
+/// The loaders mirror those on [`Config`], and combining loaded configurations with
+/// [`LoadedConfig::merge`] or [`LoadedConfig::overridden_by`] keeps track of which sources the
+/// combined values came from.
+#[derive(Debug, Default, PartialEq)]
+pub struct LoadedConfig {
+    pub config: Config,
+    origins: BTreeMap<String, Vec<Origin>>,
+}
+
+impl LoadedConfig {
+    /// Loads a configuration file like [`Config::from_file`], recording the location of each key.
+    ///
+    /// Keys that come from a file named in `extends` take their origins from that file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        with_bases(path, LoadedConfig::from_single_file(path)?)
+    }
+
+    /// Loads a configuration file without resolving its `extends`.
+    pub(crate) fn from_single_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = read_file(path)?;
+        let (config, format) = parse(path, &contents)?;
+        let keys = find_keys(path, &contents, format)?;
+        Ok(LoadedConfig::from_keys(path, config, &keys))
+    }
+
+    /// Loads a configuration file without resolving its `extends`, along with the format it was
+    /// parsed as and the warnings about it. See [`Config::from_file_with_warnings`].
+    pub(crate) fn from_single_file_with_warnings(
+        path: &Path,
+    ) -> Result<(Self, Format, Vec<LoadWarning>), ConfigError> {
+        let contents = read_file(path)?;
+        let (config, format) = parse(path, &contents)?;
+        let keys = find_keys(path, &contents, format)?;
+        let raw: serde_json::Value = format.parse(path, &contents)?;
+        let warnings = collect_warnings(&config, &keys, &raw);
+        Ok((
+            LoadedConfig::from_keys(path, config, &keys),
+            format,
+            warnings,
+        ))
+    }
+
+    /// Records the location of each key set in a configuration loaded from `path`, including the
+    /// keys of each of its profiles.
+    fn from_keys(path: &Path, config: Config, keys: &[KeyOccurrence]) -> Self {
+        let origin_of = |table: &KeyTable, key: &str| {
+            let location = keys
+                .iter()
+                .find(|occurrence| occurrence.table == *table && modern_key(&occurrence.key) == key)
+                .and_then(|occurrence| occurrence.location.clone());
+            vec![Origin::File {
+                path: path.to_path_buf(),
+                location,
+            }]
+        };
+        let mut origins: BTreeMap<String, Vec<Origin>> = config
+            .set_keys()
+            .into_iter()
+            .map(|key| (String::from(key), origin_of(&KeyTable::TopLevel, key)))
+            .collect();
+        for (name, profile) in config.profile.iter().flatten() {
+            for key in profile.set_keys() {
+                origins.insert(
+                    profile_key(name, key),
+                    origin_of(&KeyTable::Profile(name.clone()), key),
+                );
+            }
+        }
+        LoadedConfig { config, origins }
+    }
+
+    /// Loads the highest precedence configuration file in the folder, along with its
+    /// `.muse/ignoreFiles` file, like [`Config::from_folder`].
+    pub fn from_folder<P: AsRef<Path>>(path: P) -> Result<Option<Self>, ConfigError> {
+        let loaded = match locate_files(&path)?.into_iter().next() {
+            Some(config_file) => Some(LoadedConfig::from_file(config_file)?),
+            None => None,
+        };
+        with_ignores_file(&path, loaded)
+    }
+
+    /// Loads and layers every configuration file in the folder like [`Config::from_folder_merged`].
+    pub fn from_folder_merged<P: AsRef<Path>>(path: P) -> Result<Option<Self>, ConfigError> {
+        let loaded = merge_files(locate_files(&path)?)?;
+        with_ignores_file(&path, loaded)
+    }
+
+    /// Reads the environment variables that start with `prefix` like [`Config::from_env`],
+    /// recording the variable that set each key.
+    pub fn from_env(prefix: &str) -> Result<Self, ConfigError> {
+        LoadedConfig::from_vars(prefix, std::env::vars_os())
+    }
+
+    /// Reads variables like [`LoadedConfig::from_env`], but takes them from `vars` instead of the
+    /// process environment.
+    pub fn from_vars<I, K, V>(prefix: &str, vars: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        let config = Config::from_vars(prefix, vars)?;
+        let origins = config
+            .set_keys()
+            .into_iter()
+            .map(|key| {
+                let variable = variable_for(prefix, key);
+                (String::from(key), vec![Origin::Environment { variable }])
+            })
+            .collect();
+        Ok(LoadedConfig { config, origins })
+    }
+
+    /// A configuration with no recorded origins.
+    pub(crate) fn without_origins(config: Config) -> Self {
+        LoadedConfig {
+            config,
+            origins: BTreeMap::new(),
+        }
+    }
+
+    /// Takes the `extends` of the configuration, along with its origins, once it is resolved.
+    pub(crate) fn take_extends(&mut self) -> Option<Vec<String>> {
+        self.origins.remove("extends");
+        self.config.extends.take()
+    }
+
+    /// Where the effective value of a key came from, with keys named as they are written in
+    /// configuration files (`jdk11`, `importantRules`). The keys of a profile are named after it,
+    /// as in `profile.nightly.jdk11`. This is `None` if the key is not set.
+    ///
+    /// For keys whose values are concatenated when merging, such as `importantRules`, this is the
+    /// source with the highest precedence; see [`LoadedConfig::origins_of`] for all of them.
+    pub fn origin_of(&self, key: &str) -> Option<&Origin> {
+        self.origins_of(key).last()
+    }
+
+    /// Every source that contributed to the value of a key, from the lowest precedence to the
+    /// highest.
+    pub fn origins_of(&self, key: &str) -> &[Origin] {
+        self.origins.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Checks the configuration with [`Config::validate`], pointing each diagnostic at where its
+    /// key is set, if that is in a file.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.config.validate();
+        for diagnostic in &mut diagnostics {
+            if let Some(Origin::File { location, .. }) = self.origin_of(diagnostic.key) {
+                diagnostic.location.clone_from(location);
+            }
+        }
+        diagnostics
+    }
+
+    /// Layers `other` on top of `self` with [`Config::merge`], keeping the origins of every value
+    /// that the merged configuration uses.
+    pub fn merge(mut self, other: LoadedConfig) -> LoadedConfig {
+        for (key, origins) in other.origins {
+            // Profiles with the same name are merged too, so their keys follow the same rules.
+            let leaf = key.rsplit('.').next().unwrap_or(&key);
+            if CONCATENATED_KEYS.contains(&leaf) {
+                self.origins.entry(key).or_default().extend(origins);
+            } else {
+                self.origins.insert(key, origins);
+            }
+        }
+        LoadedConfig {
+            config: self.config.merge(other.config),
+            origins: self.origins,
+        }
+    }
+
+    /// Selects a profile with [`Config::with_profile`]. The keys the profile sets take their
+    /// origins from where they are written in its `[profile.<name>]` tables.
+    pub fn with_profile(self, name: &str) -> Result<LoadedConfig, ConfigError> {
+        let config = self.config.with_profile(name)?;
+        let prefix = profile_key(name, "");
+        let mut origins = BTreeMap::new();
+        let mut profile_origins = BTreeMap::new();
+        for (key, key_origins) in self.origins {
+            if let Some(key) = key.strip_prefix(&prefix) {
+                profile_origins.insert(String::from(key), key_origins);
+            } else if key != "profile" && !key.starts_with("profile.") {
+                origins.insert(key, key_origins);
+            }
+        }
+        origins.extend(profile_origins);
+        Ok(LoadedConfig { config, origins })
+    }
+
+    /// Replaces every field of `self` that is set in `overrides` with
+    /// [`Config::overridden_by`], along with its origins.
+    pub fn overridden_by(mut self, overrides: LoadedConfig) -> LoadedConfig {
+        if overrides.config.profile.is_some() {
+            self.origins
+                .retain(|key, _origins| !key.starts_with("profile."));
+        }
+        self.origins.extend(overrides.origins);
+        LoadedConfig {
+            config: self.config.overridden_by(overrides.config),
+            origins: self.origins,
+        }
+    }
+}
+
+/// The name that the origins of a profile's key are recorded under.
+fn profile_key(name: &str, key: &str) -> String {
+    format!("profile.{}.{}", name, key)
+}
+
+/// Loads configuration files and layers them from the lowest precedence to the highest, where
+/// the files are given from the highest precedence to the lowest.
+pub(crate) fn merge_files(config_files: Vec<PathBuf>) -> Result<Option<LoadedConfig>, ConfigError> {
+    config_files
+        .into_iter()
+        .rev()
+        .try_fold(None, |merged: Option<LoadedConfig>, config_file| {
+            let loaded = LoadedConfig::from_file(config_file)?;
+            Ok(Some(match merged {
+                Some(merged) => merged.merge(loaded),
+                None => loaded,
+            }))
+        })
+}
+
+/// Appends the folder's `.muse/ignoreFiles` entries, if any, to the configuration's `ignoreFiles`.
+pub(crate) fn with_ignores_file<P: AsRef<Path>>(
+    folder: P,
+    loaded: Option<LoadedConfig>,
+) -> Result<Option<LoadedConfig>, ConfigError> {
+    let ignores_file = match locate_ignores_file(folder) {
+        Some(ignores_file) => ignores_file,
+        None => return Ok(loaded),
+    };
+    let ignore_files = trim_lines(&std::fs::read_to_string(&ignores_file)?);
+    if ignore_files.is_empty() {
+        return Ok(loaded);
+    }
+    let ignores = LoadedConfig {
+        config: Config {
+            ignore_files: Some(ignore_files),
+            ..Default::default()
+        },
+        origins: BTreeMap::from([(
+            String::from("ignoreFiles"),
+            vec![Origin::IgnoresFile { path: ignores_file }],
+        )]),
+    };
+    Ok(Some(match loaded {
+        Some(loaded) => loaded.merge(ignores),
+        None => ignores,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::DEFAULT_ENV_PREFIX;
+    use temp_testdir::TempDir;
+
+    fn line_of(origin: Option<&Origin>) -> Option<(&Path, usize)> {
+        match origin {
+            Some(Origin::File {
+                path,
+                location: Some(location),
+            }) => Some((path, location.line)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn it_records_where_each_key_is_in_a_file() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "build = \"make\"\n\njdk11 = true\n")
+            .expect("Failed to write .lift.toml");
+
+        let loaded = LoadedConfig::from_file(&path).expect("Failed to load file");
+        assert_eq!(loaded.config.jdk_11, Some(true));
+        assert_eq!(
+            line_of(loaded.origin_of("jdk11")),
+            Some((path.as_path(), 3))
+        );
+        assert_eq!(
+            line_of(loaded.origin_of("build")),
+            Some((path.as_path(), 1))
+        );
+        assert_eq!(loaded.origin_of("setup"), None);
+        assert!(loaded.origins_of("setup").is_empty());
+    }
+
+    #[test]
+    fn it_records_the_top_level_key_after_a_nested_one_of_the_same_name() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.json");
+        std::fs::write(
+            &path,
+            "{\n  \"profile\": {\"ci\": {\"build\": \"x\"}},\n  \"build\": \"y\"\n}",
+        )
+        .expect("Failed to write .lift.json");
+
+        let loaded = LoadedConfig::from_file(&path).expect("Failed to load file");
+        assert_eq!(
+            line_of(loaded.origin_of("build")),
+            Some((path.as_path(), 3))
+        );
+        assert_eq!(
+            line_of(loaded.origin_of("profile.ci.build")),
+            Some((path.as_path(), 2))
+        );
+    }
+
+    #[test]
+    fn it_points_diagnostics_at_the_keys_they_are_about() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "build = \"\"\n\nandroidVersion = 0\n")
+            .expect("Failed to write .lift.toml");
+
+        let loaded = LoadedConfig::from_file(&path).expect("Failed to load file");
+        let diagnostics = loaded.validate();
+        let lines: Vec<Option<usize>> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.location.as_ref().map(|location| location.line))
+            .collect();
+        assert_eq!(lines, vec![Some(1), Some(3)]);
+        assert_eq!(
+            diagnostics[1].render(),
+            format!(
+                "error[android-version-out-of-range]: {}:3:1: `androidVersion` is 0, but must be an Android API level from 1 to 36\n  \
+                 |\n3 | androidVersion = 0\n  | ^^^^^^^^^^^^^^",
+                path.display()
+            )
+        );
+        assert_eq!(loaded.config.validate()[1].location, None);
+    }
+
+    #[test]
+    fn it_records_every_file_that_contributes_to_a_merged_value() {
+        let temp = TempDir::default();
+        std::fs::create_dir_all(temp.join(".lift")).expect("Failed to create .lift directory");
+        std::fs::create_dir_all(temp.join(".muse")).expect("Failed to create .muse directory");
+        let high = temp.join(".lift/config.toml");
+        let low = temp.join(".lift.toml");
+        let ignores = temp.join(".muse/ignoreFiles");
+        std::fs::write(&high, "importantRules = [\"B\"]\njdk11 = true\n")
+            .expect("Failed to write .lift/config.toml");
+        std::fs::write(
+            &low,
+            "jdk11 = false\nimportantRules = [\"A\"]\nignoreFiles = \"build/\"\n",
+        )
+        .expect("Failed to write .lift.toml");
+        std::fs::write(&ignores, "target/\n").expect("Failed to write .muse/ignoreFiles");
+
+        let loaded = LoadedConfig::from_folder_merged(&*temp)
+            .expect("Failed to load folder")
+            .expect("Expected a config");
+        assert_eq!(
+            line_of(loaded.origin_of("jdk11")),
+            Some((high.as_path(), 2))
+        );
+        let important_rules: Vec<Option<&Path>> = loaded
+            .origins_of("importantRules")
+            .iter()
+            .map(Origin::path)
+            .collect();
+        assert_eq!(
+            important_rules,
+            vec![Some(low.as_path()), Some(high.as_path())]
+        );
+        assert_eq!(
+            loaded.origin_of("ignoreFiles"),
+            Some(&Origin::IgnoresFile { path: ignores })
+        );
+        assert_eq!(loaded.origins_of("ignoreFiles").len(), 2);
+    }
+
+    #[test]
+    fn it_records_the_profile_that_sets_a_key() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "build = \"make\"\njdk11 = false\n\n[profile.nightly]\njdk11 = true\n",
+        )
+        .expect("Failed to write .lift.toml");
+
+        let loaded = LoadedConfig::from_file(&path)
+            .expect("Failed to load file")
+            .with_profile("nightly")
+            .expect("Failed to select the profile");
+        assert_eq!(loaded.config.jdk_11, Some(true));
+        assert_eq!(
+            line_of(loaded.origin_of("jdk11")),
+            Some((path.as_path(), 5))
+        );
+        assert_eq!(
+            line_of(loaded.origin_of("build")),
+            Some((path.as_path(), 1))
+        );
+        assert_eq!(loaded.origin_of("profile"), None);
+    }
+
+    #[test]
+    fn it_records_each_key_of_each_profile() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(
+            &path,
+            "build = \"make\"\n\n[profile.a]\njdk11 = true\n\n[profile.b]\nbuild = \"gradle\"\njdk11 = false\n",
+        )
+        .expect("Failed to write .lift.toml");
+
+        let loaded = LoadedConfig::from_file(&path).expect("Failed to load file");
+        assert_eq!(
+            line_of(loaded.origin_of("profile.a.jdk11")),
+            Some((path.as_path(), 4))
+        );
+        let b = loaded
+            .with_profile("b")
+            .expect("Failed to select the profile");
+        assert_eq!(b.config.jdk_11, Some(false));
+        assert_eq!(line_of(b.origin_of("jdk11")), Some((path.as_path(), 8)));
+        assert_eq!(line_of(b.origin_of("build")), Some((path.as_path(), 7)));
+        assert_eq!(b.origin_of("profile.a.jdk11"), None);
+
+        let json = temp.join(".lift.json");
+        std::fs::write(
+            &json,
+            "{\n  \"profile\": {\n    \"a\": {\"jdk11\": true},\n    \"b\": {\n      \"jdk11\": false\n    }\n  }\n}",
+        )
+        .expect("Failed to write .lift.json");
+        let b = LoadedConfig::from_file(&json)
+            .expect("Failed to load file")
+            .with_profile("b")
+            .expect("Failed to select the profile");
+        assert_eq!(line_of(b.origin_of("jdk11")), Some((json.as_path(), 5)));
+    }
+
+    #[test]
+    fn it_records_the_variables_that_override_a_file() {
+        let temp = TempDir::default();
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, "jdk11 = false\nbuild = \"make\"\n")
+            .expect("Failed to write .lift.toml");
+
+        let loaded = LoadedConfig::from_file(&path)
+            .expect("Failed to load file")
+            .overridden_by(
+                LoadedConfig::from_vars(DEFAULT_ENV_PREFIX, [("LIFT_JDK11", "true")])
+                    .expect("Failed to read the variables"),
+            );
+        assert_eq!(loaded.config.jdk_11, Some(true));
+        let origin = loaded.origin_of("jdk11").expect("Expected an origin");
+        assert_eq!(origin.to_string(), "environment variable LIFT_JDK11");
+        assert_eq!(
+            line_of(loaded.origin_of("build")),
+            Some((path.as_path(), 2))
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::{fmt, ops::Range, path::PathBuf};
+
+/// A position within a configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+
+    /// The byte range in the file's contents.
+    pub span: Range<usize>,
+
+    /// The 1-based line that the span starts on.
+    pub line: usize,
+
+    /// The 1-based column, in characters, that the span starts on.
+    pub column: usize,
+
+    /// The text of the line that the span starts on, without its line ending, kept so that the
+    /// location can be quoted without reading the file again.
+    pub line_text: Box<str>,
+}
+
+impl Location {
+    /// Builds a location from a byte span, working out its line and column from the contents.
+    pub fn from_span<P: Into<PathBuf>>(path: P, contents: &str, span: Range<usize>) -> Self {
+        let start = floor_char_boundary(contents, span.start);
+        let before = &contents[..start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_text = contents[line_start..].lines().next().unwrap_or_default();
+        Location {
+            path: path.into(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            line_text: Box::from(line_text),
+            span,
+        }
+    }
+
+    /// Builds a location from a 1-based line and a 1-based column counted in bytes, as parsers
+    /// that do not report byte offsets give them. The span is empty, pointing between characters.
+    pub(crate) fn from_line_column<P: Into<PathBuf>>(
+        path: P,
+        contents: &str,
+        line: usize,
+        column: usize,
+    ) -> Self {
+        let line_start: usize = contents
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let offset = floor_char_boundary(contents, line_start + column.saturating_sub(1));
+        Location::from_span(path, contents, offset..offset)
+    }
+
+    /// Quotes the line that the location starts on, with carets under the span in the style of
+    /// rustc:
+    ///
+    /// ```text
+    ///   |
+    /// 3 | importantRule = []
+    ///   | ^^^^^^^^^^^^^
+    /// ```
+    ///
+    /// Spans that run onto later lines are only underlined to the end of the first line, and
+    /// empty spans get a single caret.
+    pub fn snippet(&self) -> String {
+        let text = &self.line_text;
+        let start = text
+            .char_indices()
+            .nth(self.column.saturating_sub(1))
+            .map_or(text.len(), |(index, _)| index);
+        let end = floor_char_boundary(text, start + self.span.len());
+        let underlined = text[start..end].chars().count();
+        let gutter = " ".repeat(self.line.to_string().len());
+        format!(
+            "{gutter} |\n{line} | {text}\n{gutter} | {padding}{carets}",
+            gutter = gutter,
+            line = self.line,
+            text = text,
+            padding = " ".repeat(self.column.saturating_sub(1)),
+            carets = "^".repeat(underlined.max(1)),
+        )
+    }
+}
+
+/// Renders a diagnostic like `rustc` does: a `severity: message` header, then a snippet of the
+/// line the location points into. The message is expected to name the location already.
+pub(crate) fn render_diagnostic(
+    severity: &str,
+    message: &str,
+    location: Option<&Location>,
+) -> String {
+    match location {
+        Some(location) => format!("{}: {}\n{}", severity, message, location.snippet()),
+        None => format!("{}: {}", severity, message),
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+fn floor_char_boundary(contents: &str, index: usize) -> usize {
+    let mut index = index.min(contents.len());
+    while !contents.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_line_and_column_of_a_span() {
+        let contents = "setup = \"make\"\n\nimportantRule = []\n";
+        let location = Location::from_span(".lift.toml", contents, 16..29);
+        assert_eq!(location.line, 3);
+        assert_eq!(location.column, 1);
+        assert_eq!(location.to_string(), ".lift.toml:3:1");
+    }
+
+    #[test]
+    fn it_finds_the_span_of_a_line_and_column() {
+        let contents = "{\n  \"setup\": 3\n}";
+        let location = Location::from_line_column(".lift.json", contents, 2, 12);
+        assert_eq!(location.span, 13..13);
+        assert_eq!((location.line, location.column), (2, 12));
+    }
+
+    #[test]
+    fn it_underlines_the_span_in_a_snippet() {
+        let contents = "setup = \"make\"\n\nimportantRule = []\n";
+        let location = Location::from_span(".lift.toml", contents, 16..29);
+        assert_eq!(
+            location.snippet(),
+            "  |\n3 | importantRule = []\n  | ^^^^^^^^^^^^^"
+        );
+
+        let location = Location::from_span(".lift.toml", contents, 24..24);
+        assert_eq!(
+            location.snippet(),
+            "  |\n3 | importantRule = []\n  |         ^"
+        );
+
+        let location = Location::from_span(".lift.toml", contents, 16..40);
+        assert_eq!(
+            location.snippet(),
+            "  |\n3 | importantRule = []\n  | ^^^^^^^^^^^^^^^^^^"
+        );
+    }
+}
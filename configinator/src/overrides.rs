@@ -0,0 +1,184 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::ignore_files::IgnoreMatcher;
+
+/// Settings that apply to part of the repository, written as an `[[overrides]]` table:
+///
+/// ```toml
+/// [[overrides]]
+/// paths = ["android/"]
+/// androidVersion = 28
+/// build = "gradlew assembleDebug"
+/// ```
+///
+/// `paths` are gitignore-style patterns relative to the repository root, with the same semantics
+/// as `ignoreFiles`, so `android/` matches the `android` directory and everything beneath it. The
+/// rest of the table can set any key, including further `overrides`.
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "schema",
+    schemars(
+        title = "Override",
+        description = "Settings that apply to the paths matched by `paths`, on top of the top-level settings."
+    )
+)]
+pub struct Override {
+    /// The gitignore-style patterns, relative to the repository root, of the paths the settings
+    /// apply to.
+    pub paths: Vec<String>,
+
+    #[serde(flatten)]
+    #[cfg_attr(feature = "schema", schemars(with = "crate::schema::NestedConfig"))]
+    pub config: Config,
+}
+
+impl Override {
+    /// Checks whether a path, given relative to the repository root, is covered by `paths`,
+    /// either itself or through one of its parent directories. Invalid patterns are skipped.
+    pub fn applies_to<P: AsRef<Path>>(&self, relative_path: P) -> bool {
+        let relative_path = relative_path.as_ref();
+        let matcher = IgnoreMatcher::new_lenient(&self.paths.join("\n"));
+        // The path may name a directory, which directory-only patterns such as `android/` only
+        // match when it is checked as one.
+        matcher.is_ignored(relative_path) || matcher.is_dir_ignored(relative_path)
+    }
+}
+
+/// Layers the overrides that apply to `relative_path` on top of `config`, in the order they are
+/// written, resolving any overrides nested within them for the same path.
+pub(crate) fn for_path(config: &Config, relative_path: &Path) -> Config {
+    let base = Config {
+        overrides: None,
+        ..config.clone()
+    };
+    config
+        .overrides
+        .iter()
+        .flatten()
+        .filter(|entry| entry.applies_to(relative_path))
+        .fold(base, |effective, entry| {
+            effective.merge(for_path(&entry.config, relative_path))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_testdir::TempDir;
+
+    const MONOREPO: &str = r#"
+build = "make"
+importantRules = ["NULL_DEREFERENCE"]
+tools = ["infer", "eslint"]
+
+[[overrides]]
+paths = ["android/"]
+androidVersion = 28
+build = "gradlew assembleDebug"
+
+[[overrides]]
+paths = ["web/", "docs/site/"]
+tools = ["eslint"]
+importantRules = ["no-extra-boolean-cast"]
+
+[[overrides]]
+paths = ["android/legacy/"]
+jdk11 = true
+"#;
+
+    fn monorepo(temp: &TempDir) -> Config {
+        let path = temp.join(".lift.toml");
+        std::fs::write(&path, MONOREPO).expect("Failed to write .lift.toml");
+        Config::from_file(&path).expect("Failed to parse file")
+    }
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|value| String::from(*value)).collect())
+    }
+
+    #[test]
+    fn it_should_apply_the_overrides_for_a_path() {
+        let temp = TempDir::default();
+        let config = monorepo(&temp);
+
+        let android = config.for_path("android/app");
+        assert_eq!(android.build.as_deref(), Some("gradlew assembleDebug"));
+        assert_eq!(android.android_version, Some(28));
+        assert_eq!(android.tools, strings(&["infer", "eslint"]));
+        assert_eq!(android.overrides, None);
+        assert_eq!(config.for_path("android").android_version, Some(28));
+
+        let web = config.for_path("web");
+        assert_eq!(web.build.as_deref(), Some("make"));
+        assert_eq!(web.tools, strings(&["eslint"]));
+        assert_eq!(
+            web.important_rules,
+            strings(&["NULL_DEREFERENCE", "no-extra-boolean-cast"])
+        );
+        assert_eq!(
+            config.for_path("docs/site/index.md").tools,
+            strings(&["eslint"])
+        );
+    }
+
+    #[test]
+    fn it_should_layer_overrides_in_order() {
+        let temp = TempDir::default();
+        let config = monorepo(&temp);
+
+        let legacy = config.for_path("android/legacy/src/Main.java");
+        assert_eq!(legacy.android_version, Some(28));
+        assert_eq!(legacy.jdk_11, Some(true));
+        assert_eq!(config.for_path("android/app").jdk_11, None);
+    }
+
+    #[test]
+    fn it_should_use_the_top_level_settings_elsewhere() {
+        let temp = TempDir::default();
+        let config = monorepo(&temp);
+
+        let expected = Config {
+            build: Some(String::from("make")),
+            important_rules: strings(&["NULL_DEREFERENCE"]),
+            tools: strings(&["infer", "eslint"]),
+            ..Default::default()
+        };
+        assert_eq!(config.for_path("services/api"), expected);
+        assert_eq!(config.for_path("docs/guide.md"), expected);
+        assert_eq!(config.for_path("webapp"), expected);
+    }
+
+    #[test]
+    fn it_should_resolve_nested_overrides() {
+        let config = Config {
+            overrides: Some(vec![Override {
+                paths: vec![String::from("services/")],
+                config: Config {
+                    build: Some(String::from("cargo build")),
+                    overrides: Some(vec![Override {
+                        paths: vec![String::from("services/legacy/")],
+                        config: Config {
+                            build: Some(String::from("make")),
+                            ..Default::default()
+                        },
+                    }]),
+                    ..Default::default()
+                },
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.for_path("services/api").build.as_deref(),
+            Some("cargo build")
+        );
+        assert_eq!(
+            config.for_path("services/legacy").build.as_deref(),
+            Some("make")
+        );
+        assert_eq!(config.for_path("legacy").build, None);
+    }
+}
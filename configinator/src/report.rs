@@ -0,0 +1,244 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::config::Config;
+use crate::format::Format;
+use crate::keys::{modern_key, suggest, KeyOccurrence, KeyTable, DEPRECATED_KEYS, KEYS};
+use crate::location::{render_diagnostic, Location};
+
+/// A configuration along with the problems found while loading it that did not stop it loading.
+#[derive(Debug, PartialEq)]
+pub struct LoadReport {
+    pub config: Config,
+    pub format: Format,
+    pub warnings: Vec<LoadWarning>,
+}
+
+/// A problem with a configuration file that is worth reporting but not worth failing over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadWarning {
+    /// A key that `Config` does not recognize, which is ignored.
+    UnknownKey {
+        key: String,
+        location: Option<Location>,
+        suggestion: Option<String>,
+    },
+
+    /// A key from an older version of the schema, which is read as `replacement`.
+    DeprecatedKey {
+        key: String,
+        location: Option<Location>,
+        replacement: String,
+    },
+
+    /// A key from an older version of the schema that is ignored, because its `replacement` is
+    /// also set.
+    SupersededKey {
+        key: String,
+        location: Option<Location>,
+        replacement: String,
+    },
+
+    /// A value written in a shape from an older version of the schema, which is converted to the
+    /// `expected` shape.
+    DeprecatedShape {
+        key: String,
+        location: Option<Location>,
+        expected: String,
+    },
+
+    /// A list that contains the same entry more than once.
+    DuplicateEntry {
+        key: String,
+        location: Option<Location>,
+        value: String,
+    },
+
+    /// A `setup` or `build` command that is empty or only whitespace.
+    EmptyString {
+        key: String,
+        location: Option<Location>,
+    },
+}
+
+impl LoadWarning {
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            LoadWarning::UnknownKey { location, .. }
+            | LoadWarning::DeprecatedKey { location, .. }
+            | LoadWarning::SupersededKey { location, .. }
+            | LoadWarning::DeprecatedShape { location, .. }
+            | LoadWarning::DuplicateEntry { location, .. }
+            | LoadWarning::EmptyString { location, .. } => location.as_ref(),
+        }
+    }
+
+    /// Describes the warning without its location.
+    pub fn message(&self) -> String {
+        match self {
+            LoadWarning::UnknownKey {
+                key,
+                suggestion: Some(suggestion),
+                ..
+            } => format!(
+                "Unknown key `{}` is ignored, did you mean `{}`?",
+                key, suggestion
+            ),
+            LoadWarning::UnknownKey { key, .. } => format!("Unknown key `{}` is ignored", key),
+            LoadWarning::DeprecatedKey {
+                key, replacement, ..
+            } => format!(
+                "Deprecated key `{}` was migrated, use `{}` instead",
+                key, replacement
+            ),
+            LoadWarning::SupersededKey {
+                key, replacement, ..
+            } => format!(
+                "Deprecated key `{}` is ignored, as `{}` is also set",
+                key, replacement
+            ),
+            LoadWarning::DeprecatedShape { key, expected, .. } => format!(
+                "Deprecated shape of `{}` was migrated, use {} instead",
+                key, expected
+            ),
+            LoadWarning::DuplicateEntry { key, value, .. } => {
+                format!("`{}` lists `{}` more than once", key, value)
+            }
+            LoadWarning::EmptyString { key, .. } => format!("`{}` is empty", key),
+        }
+    }
+
+    /// Renders the warning like a compiler diagnostic, quoting the line of the file it is about.
+    /// See [`ConfigError::render`](crate::error::ConfigError::render).
+    pub fn render(&self) -> String {
+        render_diagnostic("warning", &self.to_string(), self.location())
+    }
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.location() {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}", self.message())
+    }
+}
+
+/// Checks the keys and values of a loaded configuration for anything worth warning about.
+///
+/// `raw` is the file as it was written, before it was read into `config`, so that values written
+/// in deprecated shapes can be recognized. The top-level table is checked first, then each
+/// profile and each entry of `overrides`.
+pub(crate) fn collect_warnings(
+    config: &Config,
+    keys: &[KeyOccurrence],
+    raw: &serde_json::Value,
+) -> Vec<LoadWarning> {
+    let mut warnings = table_warnings(config, keys, &KeyTable::TopLevel, Some(raw));
+    for (name, profile) in config.profile.iter().flatten() {
+        let raw_profile = raw.get("profile").and_then(|profiles| profiles.get(name));
+        let table = KeyTable::Profile(name.clone());
+        warnings.extend(table_warnings(profile, keys, &table, raw_profile));
+    }
+    for (index, entry) in config.overrides.iter().flatten().enumerate() {
+        let raw_entry = raw.get("overrides").and_then(|entries| entries.get(index));
+        let table = KeyTable::Override(index);
+        warnings.extend(table_warnings(&entry.config, keys, &table, raw_entry));
+    }
+    warnings
+}
+
+/// Checks the keys of one table of a configuration file, and the values they were read into.
+fn table_warnings(
+    config: &Config,
+    keys: &[KeyOccurrence],
+    table: &KeyTable,
+    raw: Option<&serde_json::Value>,
+) -> Vec<LoadWarning> {
+    let keys: Vec<&KeyOccurrence> = keys
+        .iter()
+        .filter(|occurrence| occurrence.table == *table)
+        .collect();
+    // The modern key is the one that was read when both it and its deprecated name are set.
+    let occurrence_of = |key: &str| {
+        keys.iter()
+            .find(|occurrence| occurrence.key == key)
+            .or_else(|| {
+                keys.iter()
+                    .find(|occurrence| modern_key(&occurrence.key) == key)
+            })
+            .copied()
+    };
+    let location_of =
+        |key: &str| occurrence_of(key).and_then(|occurrence| occurrence.location.clone());
+    // The entries of `overrides` also name the paths they apply to.
+    let is_known = |key: &str| {
+        KEYS.contains(&key) || (matches!(table, KeyTable::Override(_)) && key == "paths")
+    };
+    let mut warnings = Vec::new();
+
+    for occurrence in &keys {
+        if is_known(&occurrence.key) {
+            continue;
+        }
+        let deprecated = DEPRECATED_KEYS
+            .iter()
+            .find(|(deprecated, _replacement)| *deprecated == occurrence.key);
+        let is_set = |key: &str| keys.iter().any(|occurrence| occurrence.key == key);
+        warnings.push(match deprecated {
+            Some((_deprecated, replacement)) if is_set(replacement) => LoadWarning::SupersededKey {
+                key: occurrence.key.clone(),
+                location: occurrence.location.clone(),
+                replacement: String::from(*replacement),
+            },
+            Some((_deprecated, replacement)) => LoadWarning::DeprecatedKey {
+                key: occurrence.key.clone(),
+                location: occurrence.location.clone(),
+                replacement: String::from(*replacement),
+            },
+            None => LoadWarning::UnknownKey {
+                key: occurrence.key.clone(),
+                location: occurrence.location.clone(),
+                suggestion: suggest(&occurrence.key).map(String::from),
+            },
+        });
+    }
+
+    if let Some(occurrence) = occurrence_of("ignoreFiles") {
+        if raw
+            .and_then(|raw| raw.get(&occurrence.key))
+            .is_some_and(serde_json::Value::is_array)
+        {
+            warnings.push(LoadWarning::DeprecatedShape {
+                key: String::from("ignoreFiles"),
+                location: occurrence.location.clone(),
+                expected: String::from("a string with one pattern per line"),
+            });
+        }
+    }
+
+    for (key, values) in config.lists() {
+        for value in values.iter().duplicates() {
+            warnings.push(LoadWarning::DuplicateEntry {
+                key: String::from(key),
+                location: location_of(key),
+                value: value.clone(),
+            });
+        }
+    }
+
+    for (key, value) in [("setup", &config.setup), ("build", &config.build)] {
+        if value
+            .as_deref()
+            .is_some_and(|value| value.trim().is_empty())
+        {
+            warnings.push(LoadWarning::EmptyString {
+                key: String::from(key),
+                location: location_of(key),
+            });
+        }
+    }
+
+    warnings
+}
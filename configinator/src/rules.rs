@@ -0,0 +1,132 @@
+/// How findings for a rule should be reported, according to `importantRules` and `ignoreRules`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleDisposition {
+    /// The rule matches `ignoreRules`, so its findings are suppressed.
+    Ignored,
+    /// The rule matches `importantRules`, so its findings are promoted.
+    Important,
+    /// The rule matches neither list.
+    Normal,
+}
+
+/// Classifies a rule against the `importantRules` and `ignoreRules` lists.
+///
+/// Entries may use `*` to match any run of characters (`NULL_*`). When a rule matches both lists,
+/// the more specific entry wins, where specificity is the number of non-wildcard characters; an
+/// exact rule ID is therefore always more specific than a wildcard that matches it. If the best
+/// entries in each list are equally specific, the rule is ignored.
+pub(crate) fn classify(
+    important_rules: &[String],
+    ignore_rules: &[String],
+    rule_id: &str,
+) -> RuleDisposition {
+    let important = best_match(important_rules, rule_id);
+    let ignored = best_match(ignore_rules, rule_id);
+    match (important, ignored) {
+        (Some(important), Some(ignored)) if important > ignored => RuleDisposition::Important,
+        (_, Some(_)) => RuleDisposition::Ignored,
+        (Some(_), None) => RuleDisposition::Important,
+        (None, None) => RuleDisposition::Normal,
+    }
+}
+
+/// Returns the specificity of the most specific pattern that matches the rule.
+fn best_match(patterns: &[String], rule_id: &str) -> Option<usize> {
+    patterns
+        .iter()
+        .filter(|pattern| matches(pattern, rule_id))
+        .map(|pattern| pattern.chars().filter(|c| *c != '*').count())
+        .max()
+}
+
+fn matches(pattern: &str, rule_id: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match rule_id.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let middle: Vec<&str> = parts.collect();
+    match middle.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(index) => rest = &rest[index + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.len() >= last.len() && rest.ends_with(last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(rules: &[&str]) -> Vec<String> {
+        rules.iter().map(|rule| String::from(*rule)).collect()
+    }
+
+    #[test]
+    fn it_matches_wildcards() {
+        assert!(matches("NULL_DEREFERENCE", "NULL_DEREFERENCE"));
+        assert!(!matches("NULL_DEREFERENCE", "NULL_DEREFERENCE_2"));
+        assert!(matches("NULL_*", "NULL_DEREFERENCE"));
+        assert!(matches("*_LEAK", "RESOURCE_LEAK"));
+        assert!(matches("no-*-cast", "no-extra-boolean-cast"));
+        assert!(matches("*", "anything"));
+        assert!(!matches("NULL_*", "RESOURCE_LEAK"));
+        assert!(!matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn it_classifies_rules_in_a_single_list() {
+        let important = rules(&["NULL_DEREFERENCE"]);
+        let ignored = rules(&["no-*"]);
+        assert_eq!(
+            classify(&important, &ignored, "NULL_DEREFERENCE"),
+            RuleDisposition::Important
+        );
+        assert_eq!(
+            classify(&important, &ignored, "no-extra-boolean-cast"),
+            RuleDisposition::Ignored
+        );
+        assert_eq!(
+            classify(&important, &ignored, "RESOURCE_LEAK"),
+            RuleDisposition::Normal
+        );
+    }
+
+    #[test]
+    fn it_prefers_the_more_specific_match_when_in_both_lists() {
+        let important = rules(&["NULL_DEREFERENCE", "no-*"]);
+        let ignored = rules(&["NULL_*", "no-extra-boolean-cast"]);
+        assert_eq!(
+            classify(&important, &ignored, "NULL_DEREFERENCE"),
+            RuleDisposition::Important
+        );
+        assert_eq!(
+            classify(&important, &ignored, "NULL_TEST_ASSERTION"),
+            RuleDisposition::Ignored
+        );
+        assert_eq!(
+            classify(&important, &ignored, "no-extra-boolean-cast"),
+            RuleDisposition::Ignored
+        );
+        assert_eq!(
+            classify(&important, &ignored, "no-unused-vars"),
+            RuleDisposition::Important
+        );
+    }
+
+    #[test]
+    fn it_ignores_rules_that_are_equally_specific_in_both_lists() {
+        let both = rules(&["RESOURCE_LEAK"]);
+        assert_eq!(
+            classify(&both, &both, "RESOURCE_LEAK"),
+            RuleDisposition::Ignored
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{
+    InstanceType, Metadata, NumberValidation, Schema, SchemaObject, SubschemaValidation,
+};
+use schemars::JsonSchema;
+
+use crate::config::Config;
+use crate::tools::ToolRegistry;
+use crate::validate::ANDROID_VERSIONS;
+
+/// The schema of a tool name in `tools` or `disableTools`: any string, with the built-in tools
+/// listed so that editors can offer them as completions.
+pub(crate) struct ToolName;
+
+impl JsonSchema for ToolName {
+    fn schema_name() -> String {
+        String::from("ToolName")
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        let builtin = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(
+                ToolRegistry::lift()
+                    .tools()
+                    .iter()
+                    .map(|tool| tool.name.clone().into())
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let other = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        };
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(String::from(
+                    "The name of a built-in tool, or of a custom tool named in `customTools`.",
+                )),
+                ..Default::default()
+            })),
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![builtin.into(), other.into()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// The schema of `androidVersion`: an integer within [`ANDROID_VERSIONS`].
+pub(crate) struct AndroidVersion;
+
+impl JsonSchema for AndroidVersion {
+    fn schema_name() -> String {
+        String::from("AndroidVersion")
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::Integer.into()),
+            number: Some(Box::new(NumberValidation {
+                minimum: Some(f64::from(*ANDROID_VERSIONS.start())),
+                maximum: Some(f64::from(*ANDROID_VERSIONS.end())),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// The schema of `extends`: a path, or a list of paths.
+pub(crate) struct Extends;
+
+impl JsonSchema for Extends {
+    fn schema_name() -> String {
+        String::from("Extends")
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![
+                    gen.subschema_for::<String>(),
+                    gen.subschema_for::<Vec<String>>(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// The schema of `ignoreFiles`: a string with one pattern per line, or the deprecated list of
+/// patterns that older configurations use.
+pub(crate) struct IgnoreFiles;
+
+impl JsonSchema for IgnoreFiles {
+    fn schema_name() -> String {
+        String::from("IgnoreFiles")
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut list = gen.subschema_for::<Vec<String>>().into_object();
+        list.metadata().deprecated = true;
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![gen.subschema_for::<String>(), list.into()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// The schema of the settings in a profile or an override: those of [`Config`], except that
+/// `extends` is rejected, as only the top level of a file can use it.
+pub(crate) struct NestedConfig;
+
+impl JsonSchema for NestedConfig {
+    fn schema_name() -> String {
+        String::from("NestedConfig")
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut schema = <Config as JsonSchema>::json_schema(gen).into_object();
+        // Unknown keys are allowed, so `extends` has to be rejected rather than left out.
+        schema
+            .object()
+            .properties
+            .insert(String::from("extends"), Schema::Bool(false));
+        let metadata = schema.metadata();
+        metadata.title = None;
+        metadata.description = Some(String::from(
+            "The settings of a profile or an override, which can be any top-level setting except `extends`.",
+        ));
+        schema.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::keys::KEYS;
+    use temp_testdir::TempDir;
+
+    fn schema() -> serde_json::Value {
+        serde_json::from_str(&Config::json_schema().expect("Failed to generate the schema"))
+            .expect("Failed to parse the schema")
+    }
+
+    #[test]
+    fn it_should_describe_every_key() {
+        let schema = schema();
+        let properties = schema["properties"]
+            .as_object()
+            .expect("Expected the schema to have properties");
+        let mut keys: Vec<&str> = properties.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        let mut expected = KEYS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(keys, expected);
+        assert!(properties
+            .values()
+            .all(|property| property["description"].is_string()));
+    }
+
+    #[test]
+    fn it_should_describe_the_known_tools_and_android_versions() {
+        let schema = schema();
+        let definitions = &schema["definitions"];
+        assert_eq!(
+            definitions["ToolName"]["anyOf"][0]["enum"][0],
+            serde_json::json!("infer")
+        );
+        assert_eq!(
+            definitions["AndroidVersion"]["maximum"],
+            serde_json::json!(36.0)
+        );
+    }
+
+    #[test]
+    fn it_should_accept_both_shapes_of_ignore_files() {
+        let validator =
+            jsonschema::JSONSchema::compile(&schema()).expect("Failed to compile the schema");
+        let temp = TempDir::default();
+        for (name, contents) in [
+            ("string.toml", "ignoreFiles = \"build/\\nsrc/test/\""),
+            ("list.toml", "ignoreFiles = [\"build/\", \"src/test/\"]"),
+        ] {
+            let path = temp.join(name);
+            std::fs::write(&path, contents).expect("Failed to write config");
+            let config = Config::from_file(&path).expect("Failed to load config");
+            assert_eq!(config.ignore_files.as_deref(), Some("build/\nsrc/test/"));
+            let raw: serde_json::Value = toml::from_str(contents).expect("Failed to parse config");
+            assert!(validator.is_valid(&raw), "Expected {} to be valid", name);
+        }
+        let ignore_files = &schema()["definitions"]["IgnoreFiles"]["anyOf"];
+        assert_eq!(ignore_files[0]["deprecated"], serde_json::Value::Null);
+        assert_eq!(ignore_files[1]["deprecated"], serde_json::json!(true));
+    }
+
+    #[test]
+    fn it_should_only_allow_extends_at_the_top_level() {
+        let validator =
+            jsonschema::JSONSchema::compile(&schema()).expect("Failed to compile the schema");
+        assert!(validator.is_valid(&serde_json::json!({"extends": "base.toml"})));
+        assert!(!validator.is_valid(&serde_json::json!({
+            "profile": {"nightly": {"extends": "base.toml"}}
+        })));
+        assert!(!validator.is_valid(&serde_json::json!({
+            "overrides": [{"paths": ["web/"], "extends": "base.toml"}]
+        })));
+        assert!(validator.is_valid(&serde_json::json!({
+            "profile": {"nightly": {"ignoreFiles": ["build/"]}},
+            "overrides": [{"paths": ["web/"], "ignoreFiles": "dist/"}]
+        })));
+    }
+
+    #[test]
+    fn it_should_match_the_committed_schema() {
+        assert_eq!(
+            Config::json_schema().expect("Failed to generate the schema"),
+            include_str!("../lift.schema.json"),
+            "lift.schema.json is out of date, regenerate it with \
+             `cargo run -q --features schema --example schema > lift.schema.json`"
+        );
+    }
+}
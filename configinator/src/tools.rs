@@ -0,0 +1,262 @@
+/// A built-in analysis tool that `tools` and `disableTools` can select.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tool {
+    pub name: String,
+
+    /// Whether the tool runs when `tools` is not set.
+    pub enabled_by_default: bool,
+}
+
+impl Tool {
+    pub fn new(name: &str, enabled_by_default: bool) -> Self {
+        Tool {
+            name: String::from(name),
+            enabled_by_default,
+        }
+    }
+}
+
+/// The set of built-in tools that configurations are resolved against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+}
+
+impl ToolRegistry {
+    pub fn new(tools: Vec<Tool>) -> Self {
+        ToolRegistry { tools }
+    }
+
+    /// The built-in Lift tools, all of which run by default.
+    pub fn lift() -> Self {
+        ToolRegistry::new(
+            [
+                "infer",
+                "errorprone",
+                "eslint",
+                "hlint",
+                "findsecbugs",
+                "clippy",
+                "shellcheck",
+            ]
+            .iter()
+            .map(|name| Tool::new(name, true))
+            .collect(),
+        )
+    }
+
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Looks up a tool by name, ignoring ASCII case.
+    pub fn get(&self, name: &str) -> Option<&Tool> {
+        self.tools
+            .iter()
+            .find(|tool| tool.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        ToolRegistry::lift()
+    }
+}
+
+/// Where a tool in the effective tool set comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolSource {
+    /// A tool in the [`ToolRegistry`].
+    Builtin,
+    /// A tool named in `customTools`.
+    Custom,
+    /// A name in `tools` or `disableTools` that is neither built-in nor custom.
+    Unknown,
+}
+
+/// A contradiction in how the configuration selects a tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolConflict {
+    /// The tool is enabled by `tools` or `customTools` but also listed in `disableTools`.
+    EnabledAndDisabled,
+    /// The tool is neither built-in nor custom, so it can never run.
+    UnknownTool,
+}
+
+/// Whether a single tool runs, and any conflicts found while deciding that.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolSelection {
+    pub name: String,
+    pub source: ToolSource,
+    pub enabled: bool,
+    pub conflicts: Vec<ToolConflict>,
+}
+
+/// Resolves the effective tool set.
+///
+/// Built-in tools are enabled if they are listed in `tools`, or, when `tools` is not set, if they
+/// are enabled by default. Custom tools are always enabled, and a built-in tool listed in
+/// `customTools` is enabled as if it were listed in `tools`. `disableTools` is then subtracted
+/// from both, and always wins over `tools` and `customTools`. Names are matched ignoring ASCII
+/// case.
+///
+/// Every built-in tool is reported, followed by the custom tools and then any unknown names, so
+/// that disabled tools and conflicts are visible as well as the tools that run.
+pub(crate) fn resolve(
+    registry: &ToolRegistry,
+    tools: Option<&[String]>,
+    disable_tools: &[String],
+    custom_tools: &[String],
+) -> Vec<ToolSelection> {
+    let mut selections: Vec<ToolSelection> = Vec::new();
+    let mut select = |name: &str, source: ToolSource, requested: bool, explicit: bool| {
+        if selections.iter().any(|s| s.name.eq_ignore_ascii_case(name)) {
+            return;
+        }
+        let disabled = contains(disable_tools, name);
+        let mut conflicts = Vec::new();
+        if explicit && disabled {
+            conflicts.push(ToolConflict::EnabledAndDisabled);
+        }
+        if source == ToolSource::Unknown {
+            conflicts.push(ToolConflict::UnknownTool);
+        }
+        selections.push(ToolSelection {
+            name: String::from(name),
+            source,
+            enabled: requested && !disabled && source != ToolSource::Unknown,
+            conflicts,
+        });
+    };
+
+    for tool in registry.tools() {
+        let custom = contains(custom_tools, &tool.name);
+        match tools {
+            Some(tools) => {
+                let listed = custom || contains(tools, &tool.name);
+                select(&tool.name, ToolSource::Builtin, listed, listed);
+            }
+            None => select(
+                &tool.name,
+                ToolSource::Builtin,
+                custom || tool.enabled_by_default,
+                custom,
+            ),
+        }
+    }
+    for custom_tool in custom_tools {
+        select(custom_tool, ToolSource::Custom, true, true);
+    }
+    let tools = tools.unwrap_or_default();
+    for name in tools.iter().chain(disable_tools) {
+        let listed = contains(tools, name);
+        select(name, ToolSource::Unknown, listed, listed);
+    }
+    selections
+}
+
+fn contains(names: &[String], name: &str) -> bool {
+    names.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| String::from(*name)).collect()
+    }
+
+    fn enabled(selections: &[ToolSelection]) -> Vec<&str> {
+        selections
+            .iter()
+            .filter(|s| s.enabled)
+            .map(|s| s.name.as_str())
+            .collect()
+    }
+
+    fn registry() -> ToolRegistry {
+        ToolRegistry::new(vec![
+            Tool::new("infer", true),
+            Tool::new("eslint", true),
+            Tool::new("hlint", false),
+        ])
+    }
+
+    #[test]
+    fn it_uses_the_defaults_when_tools_is_not_set() {
+        let selections = resolve(&registry(), None, &[], &[]);
+        assert_eq!(enabled(&selections), vec!["infer", "eslint"]);
+        assert!(selections.iter().all(|s| s.conflicts.is_empty()));
+    }
+
+    #[test]
+    fn it_subtracts_disabled_tools_from_the_defaults() {
+        let selections = resolve(&registry(), None, &names(&["eslint"]), &[]);
+        assert_eq!(enabled(&selections), vec!["infer"]);
+        assert!(selections.iter().all(|s| s.conflicts.is_empty()));
+    }
+
+    #[test]
+    fn it_only_runs_the_listed_tools_when_tools_is_set() {
+        let tools = names(&["hlint", "ESLint"]);
+        let selections = resolve(&registry(), Some(&tools), &[], &names(&["my-linter"]));
+        assert_eq!(enabled(&selections), vec!["eslint", "hlint", "my-linter"]);
+        assert_eq!(selections[3].source, ToolSource::Custom);
+    }
+
+    #[test]
+    fn it_enables_builtin_tools_listed_as_custom_tools() {
+        let tools = names(&["infer"]);
+        let selections = resolve(&registry(), Some(&tools), &[], &names(&["eslint"]));
+        assert_eq!(enabled(&selections), vec!["infer", "eslint"]);
+        assert_eq!(selections.len(), 3);
+        assert_eq!(selections[1].source, ToolSource::Builtin);
+
+        let selections = resolve(&registry(), None, &[], &names(&["HLint"]));
+        assert_eq!(enabled(&selections), vec!["infer", "eslint", "hlint"]);
+
+        let selections = resolve(&registry(), None, &names(&["hlint"]), &names(&["hlint"]));
+        assert_eq!(
+            selections[2].conflicts,
+            vec![ToolConflict::EnabledAndDisabled]
+        );
+    }
+
+    #[test]
+    fn it_reports_tools_that_are_enabled_and_disabled() {
+        let tools = names(&["infer", "eslint"]);
+        let disable_tools = names(&["eslint", "my-linter"]);
+        let selections = resolve(
+            &registry(),
+            Some(&tools),
+            &disable_tools,
+            &names(&["my-linter"]),
+        );
+        assert_eq!(enabled(&selections), vec!["infer"]);
+        let conflicted: Vec<&str> = selections
+            .iter()
+            .filter(|s| s.conflicts == vec![ToolConflict::EnabledAndDisabled])
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(conflicted, vec!["eslint", "my-linter"]);
+    }
+
+    #[test]
+    fn it_reports_unknown_tools() {
+        let tools = names(&["infer", "pylint"]);
+        let selections = resolve(&registry(), Some(&tools), &names(&["gosec"]), &[]);
+        let unknown: Vec<(&str, ToolSource, bool)> = selections
+            .iter()
+            .filter(|s| s.conflicts.contains(&ToolConflict::UnknownTool))
+            .map(|s| (s.name.as_str(), s.source, s.enabled))
+            .collect();
+        assert_eq!(
+            unknown,
+            vec![
+                ("pylint", ToolSource::Unknown, false),
+                ("gosec", ToolSource::Unknown, false)
+            ]
+        );
+    }
+}
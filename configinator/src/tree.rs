@@ -0,0 +1,360 @@
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+
+use crate::config::Config;
+use crate::error::ConfigError;
+use crate::filesystem::{locate_files, locate_ignores_file};
+use crate::ignore_files::normalize;
+use crate::loaded::{merge_files, with_ignores_file};
+
+/// Every configuration in a repository, found by searching the whole tree below its root.
+///
+/// A folder anywhere in the tree, such as `services/payments/`, may hold its own configuration
+/// files that refine the ones above it for that subtree. [`ConfigTree::resolve`] layers them for
+/// a given path.
+#[derive(Debug)]
+pub struct ConfigTree {
+    root: PathBuf,
+    levels: Vec<Level>,
+    failures: Vec<FailedFolder>,
+}
+
+/// The configuration of one folder in a [`ConfigTree`].
+#[derive(Debug, PartialEq)]
+struct Level {
+    /// The folder, relative to the root.
+    folder: PathBuf,
+
+    /// The files the configuration was loaded from, lowest precedence first.
+    files: Vec<PathBuf>,
+
+    config: Config,
+}
+
+/// A folder in a [`ConfigTree`] whose configuration could not be loaded, or that could not be
+/// searched.
+#[derive(Debug)]
+pub struct FailedFolder {
+    /// The folder, relative to the root.
+    pub folder: PathBuf,
+
+    pub error: ConfigError,
+}
+
+/// The effective configuration of a path in a [`ConfigTree`].
+#[derive(Debug, PartialEq)]
+pub struct ResolvedConfig {
+    pub config: Config,
+
+    /// The files that contributed to the configuration, in the order they were applied, from
+    /// the root of the tree down to the nearest folder.
+    pub files: Vec<PathBuf>,
+}
+
+impl ConfigTree {
+    /// Searches `root` and every folder below it for configuration files, loading each folder's
+    /// files like [`Config::from_folder_merged`], along with its `.muse/ignoreFiles` file.
+    ///
+    /// Hidden folders and the folders that git ignores, such as `target/` or `node_modules/`,
+    /// are not searched.
+    ///
+    /// A folder whose configuration cannot be loaded does not stop the search. It is left out of
+    /// the tree and reported by [`ConfigTree::failures`] instead, along with any part of the tree
+    /// that could not be searched.
+    pub fn discover<P: AsRef<Path>>(root: P) -> Result<ConfigTree, ConfigError> {
+        let root = root.as_ref();
+        if !(root.exists() && root.is_dir()) {
+            return Err(ConfigError::FolderNotFound(root.to_owned()));
+        }
+        let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        let mut levels = Vec::new();
+        let mut failures = Vec::new();
+        let walk = WalkBuilder::new(root)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        for entry in walk {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(source) => {
+                    let path = error_path(&source).unwrap_or(root).to_path_buf();
+                    failures.push(FailedFolder {
+                        folder: relative(&path),
+                        error: ConfigError::TreeSearchFailed { path, source },
+                    });
+                    continue;
+                }
+            };
+            if !entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_dir())
+            {
+                continue;
+            }
+            let folder = entry.path();
+            match load_level(folder) {
+                Ok(Some((files, config))) => levels.push(Level {
+                    folder: relative(folder),
+                    files,
+                    config,
+                }),
+                Ok(None) => {}
+                Err(error) => failures.push(FailedFolder {
+                    folder: relative(folder),
+                    error,
+                }),
+            }
+        }
+        Ok(ConfigTree {
+            root: root.to_owned(),
+            levels,
+            failures,
+        })
+    }
+
+    /// The folder the tree was searched from.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Every configuration file found in the tree, folder by folder from the root down.
+    pub fn files(&self) -> Vec<&Path> {
+        self.levels
+            .iter()
+            .flat_map(|level| level.files.iter().map(PathBuf::as_path))
+            .collect()
+    }
+
+    /// The folders that were left out of the tree because their configuration could not be
+    /// loaded or they could not be searched, from the root down.
+    pub fn failures(&self) -> &[FailedFolder] {
+        &self.failures
+    }
+
+    /// The effective configuration of a file or folder, given relative to the root.
+    ///
+    /// The configuration of every folder from the root down to the path is layered with
+    /// [`Config::merge`], so nearer folders take precedence. The `[[overrides]]` of each folder's
+    /// configuration are applied first with [`Config::for_path`], matching their `paths` against
+    /// the path relative to that folder. The patterns in each folder's `ignoreFiles` are relative
+    /// to that folder too, and are rewritten to be relative to the root before they are layered.
+    ///
+    /// This is `None` if no folder on the way to the path has a configuration. Folders listed in
+    /// [`ConfigTree::failures`] are skipped.
+    pub fn resolve<P: AsRef<Path>>(&self, relative_path: P) -> Option<ResolvedConfig> {
+        let relative_path = normalize(relative_path.as_ref());
+        self.levels
+            .iter()
+            .filter(|level| relative_path.starts_with(&level.folder))
+            .fold(None, |resolved: Option<ResolvedConfig>, level| {
+                let within = relative_path
+                    .strip_prefix(&level.folder)
+                    .unwrap_or(&relative_path);
+                let config = level.config.for_path(within).rerooted(&level.folder);
+                Some(match resolved {
+                    Some(outer) => ResolvedConfig {
+                        config: outer.config.merge(config),
+                        files: outer.files.into_iter().chain(level.files.clone()).collect(),
+                    },
+                    None => ResolvedConfig {
+                        config,
+                        files: level.files.clone(),
+                    },
+                })
+            })
+    }
+}
+
+/// Loads the configuration files of a single folder, returning them along with the merged
+/// configuration, or `None` if the folder has none.
+fn load_level(folder: &Path) -> Result<Option<(Vec<PathBuf>, Config)>, ConfigError> {
+    let config_files = locate_files(folder)?;
+    let ignores_file = locate_ignores_file(folder);
+    let files = config_files
+        .iter()
+        .rev()
+        .cloned()
+        .chain(ignores_file)
+        .collect();
+    let loaded = with_ignores_file(folder, merge_files(config_files)?)?;
+    Ok(loaded.map(|loaded| (files, loaded.config)))
+}
+
+/// The path an error from walking the tree is about, if it names one.
+fn error_path(error: &ignore::Error) -> Option<&Path> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithLineNumber { err, .. } | ignore::Error::WithDepth { err, .. } => {
+            error_path(err)
+        }
+        ignore::Error::Partial(errors) => errors.iter().find_map(error_path),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_testdir::TempDir;
+
+    fn write(root: &Path, relative: &str, contents: &str) -> PathBuf {
+        let path = root.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("Failed to create parent directory");
+        }
+        std::fs::write(&path, contents).expect("Failed to write file");
+        path
+    }
+
+    #[test]
+    fn it_should_find_every_config_in_the_tree() {
+        let temp = TempDir::default();
+        let root = write(&temp, ".lift.toml", "build = \"make\"");
+        let high = write(&temp, "services/payments/.lift/config.toml", "jdk11 = true");
+        let low = write(&temp, "services/payments/.lift.toml", "jdk11 = false");
+        let ignores = write(&temp, "web/.muse/ignoreFiles", "dist/");
+        write(&temp, "services/api/src/main.rs", "");
+
+        let tree = ConfigTree::discover(&temp).expect("Failed to search the tree");
+        assert_eq!(
+            tree.files(),
+            vec![
+                root.as_path(),
+                low.as_path(),
+                high.as_path(),
+                ignores.as_path()
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_layer_nested_configs_on_the_way_down() {
+        let temp = TempDir::default();
+        let root = write(
+            &temp,
+            ".lift.toml",
+            "build = \"make\"\nimportantRules = [\"A\"]\ntools = [\"infer\"]",
+        );
+        let services = write(&temp, "services/.lift.toml", "importantRules = [\"B\"]");
+        let payments = write(
+            &temp,
+            "services/payments/.lift.toml",
+            "build = \"gradlew build\"\njdk11 = true",
+        );
+        write(&temp, "services/payments/src/Main.java", "");
+
+        let tree = ConfigTree::discover(&temp).expect("Failed to search the tree");
+        let resolved = tree
+            .resolve("services/payments/src/Main.java")
+            .expect("Expected a config");
+        let expected = Config {
+            build: Some(String::from("gradlew build")),
+            important_rules: Some(vec![String::from("A"), String::from("B")]),
+            tools: Some(vec![String::from("infer")]),
+            jdk_11: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(resolved.config, expected);
+        assert_eq!(resolved.files, vec![root.clone(), services, payments]);
+
+        let other = tree.resolve("./web/index.js").expect("Expected a config");
+        assert_eq!(other.config.build.as_deref(), Some("make"));
+        assert_eq!(other.files, vec![root]);
+    }
+
+    #[test]
+    fn it_should_apply_overrides_relative_to_their_folder() {
+        let temp = TempDir::default();
+        write(
+            &temp,
+            "services/.lift.toml",
+            "[[overrides]]\npaths = [\"/legacy/\"]\njdk11 = true",
+        );
+
+        let tree = ConfigTree::discover(&temp).expect("Failed to search the tree");
+        let legacy = tree
+            .resolve("services/legacy/Main.java")
+            .expect("Expected a config");
+        assert_eq!(legacy.config.jdk_11, Some(true));
+        let api = tree.resolve("services/api").expect("Expected a config");
+        assert_eq!(api.config.jdk_11, None);
+        assert_eq!(tree.resolve("web"), None);
+    }
+
+    #[test]
+    fn it_should_anchor_nested_ignore_files_to_their_folder() {
+        let temp = TempDir::default();
+        write(&temp, ".lift.toml", "ignoreFiles = \"/build/\"");
+        write(
+            &temp,
+            "services/payments/.lift.toml",
+            "ignoreFiles = \"/generated/\\nsrc/test/\"",
+        );
+
+        let tree = ConfigTree::discover(&temp).expect("Failed to search the tree");
+        let payments = tree
+            .resolve("services/payments/Main.java")
+            .expect("Expected a config");
+        assert!(payments
+            .config
+            .is_file_ignored("services/payments/generated/X.java"));
+        assert!(payments
+            .config
+            .is_file_ignored("services/payments/src/test/A.java"));
+        assert!(payments.config.is_file_ignored("build/out.jar"));
+        assert!(!payments.config.is_file_ignored("generated/X.java"));
+        assert!(!payments
+            .config
+            .is_file_ignored("services/payments/build/out.jar"));
+    }
+
+    #[test]
+    fn it_should_keep_searching_past_a_broken_config() {
+        let temp = TempDir::default();
+        write(&temp, ".lift.toml", "build = \"make\"");
+        let broken = write(&temp, "services/legacy/.lift.toml", "{\n  \"build\": 1\n}");
+        write(&temp, "web/.lift.toml", "tools = [\"eslint\"]");
+
+        let tree = ConfigTree::discover(&temp).expect("Failed to search the tree");
+        let web = tree.resolve("web/index.js").expect("Expected a config");
+        assert_eq!(web.config.build.as_deref(), Some("make"));
+        assert_eq!(web.config.tools, Some(vec![String::from("eslint")]));
+        let legacy = tree.resolve("services/legacy").expect("Expected a config");
+        assert_eq!(legacy.config.build.as_deref(), Some("make"));
+
+        let failures = tree.failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].folder, Path::new("services/legacy"));
+        match &failures[0].error {
+            ConfigError::FileTomlParseFailed { path, .. } => assert_eq!(path, &broken),
+            e => panic!("Expected a FileTomlParseFailed, but got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn it_should_name_the_path_that_could_not_be_searched() {
+        let unreadable = PathBuf::from("/repo/services/secret");
+        let error = ignore::Error::WithPath {
+            path: unreadable.clone(),
+            err: Box::new(ignore::Error::Io(std::io::Error::from(
+                std::io::ErrorKind::PermissionDenied,
+            ))),
+        };
+        assert_eq!(error_path(&error), Some(unreadable.as_path()));
+        let error = ignore::Error::WithDepth {
+            depth: 2,
+            err: Box::new(ignore::Error::Partial(vec![error])),
+        };
+        assert_eq!(error_path(&error), Some(unreadable.as_path()));
+        assert_eq!(error_path(&ignore::Error::InvalidDefinition), None);
+    }
+
+    #[test]
+    fn it_should_fail_on_a_missing_root() {
+        let temp = TempDir::default();
+        let error =
+            ConfigTree::discover(temp.join("missing")).expect_err("This should have failed");
+        assert!(matches!(error, ConfigError::FolderNotFound(_)));
+    }
+}
@@ -0,0 +1,312 @@
+use std::{fmt, ops::RangeInclusive};
+
+use itertools::Itertools;
+
+use crate::config::Config;
+use crate::keys::KEYS;
+use crate::location::{render_diagnostic, Location};
+use crate::tools::ToolRegistry;
+
+/// The Android API levels that `androidVersion` can name, from the first release up to the newest
+/// one that Lift knows how to build against.
+pub const ANDROID_VERSIONS: RangeInclusive<u32> = 1..=36;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The configuration can never work, and analysis should not be started with it.
+    Error,
+    /// The configuration works, but probably not the way its author intended.
+    Warning,
+    /// The configuration works, but part of it has no effect.
+    Info,
+}
+
+/// Identifies which check produced a [`Diagnostic`].
+///
+/// The strings returned by [`DiagnosticCode::as_str`] are stable, so callers can match on them or
+/// allow specific checks without depending on the wording of messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticCode {
+    /// `androidVersion` is outside [`ANDROID_VERSIONS`].
+    AndroidVersionOutOfRange,
+    /// `setup` or `build` is empty or only whitespace, so there is no command to run.
+    EmptyCommand,
+    /// A list contains the same entry more than once.
+    DuplicateEntry,
+    /// A rule is listed in both `importantRules` and `ignoreRules`, so it is ignored.
+    RuleImportantAndIgnored,
+    /// A tool is listed in `tools` or `customTools` and also in `disableTools`, so it is disabled.
+    ToolEnabledAndDisabled,
+    /// `customTools` names a built-in tool, which enables it but belongs in `tools`.
+    CustomToolIsBuiltin,
+}
+
+impl DiagnosticCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiagnosticCode::AndroidVersionOutOfRange => "android-version-out-of-range",
+            DiagnosticCode::EmptyCommand => "empty-command",
+            DiagnosticCode::DuplicateEntry => "duplicate-entry",
+            DiagnosticCode::RuleImportantAndIgnored => "rule-important-and-ignored",
+            DiagnosticCode::ToolEnabledAndDisabled => "tool-enabled-and-disabled",
+            DiagnosticCode::CustomToolIsBuiltin => "custom-tool-is-builtin",
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A problem with the values of a configuration, found by [`Config::validate`] or
+/// [`LoadedConfig::validate`](crate::LoadedConfig::validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+
+    /// The key whose value has the problem, as written in configuration files.
+    pub key: &'static str,
+
+    pub message: String,
+
+    /// Where the key is set, if the configuration was loaded from a file and the key could be
+    /// found in it.
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, code: DiagnosticCode, key: &'static str, message: String) -> Self {
+        Diagnostic {
+            severity,
+            code,
+            key,
+            message,
+            location: None,
+        }
+    }
+
+    /// Renders the diagnostic like a compiler diagnostic, quoting the line of the file it is
+    /// about. See [`ConfigError::render`](crate::error::ConfigError::render).
+    pub fn render(&self) -> String {
+        let label = format!("{}[{}]", self.severity_str(), self.code);
+        let message = match &self.location {
+            Some(location) => format!("{}: {}", location, self.message),
+            None => self.message.clone(),
+        };
+        render_diagnostic(&label, &message, self.location.as_ref())
+    }
+
+    fn severity_str(&self) -> &'static str {
+        match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: ", self.severity_str(), self.code)?;
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Runs every check against the configuration, returning the diagnostics in the order of the
+/// keys they are about.
+pub(crate) fn validate(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (key, value) in [("setup", &config.setup), ("build", &config.build)] {
+        if value
+            .as_deref()
+            .is_some_and(|value| value.trim().is_empty())
+        {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                DiagnosticCode::EmptyCommand,
+                key,
+                format!("`{}` is empty, so there is no command to run", key),
+            ));
+        }
+    }
+
+    for (key, values) in config.lists() {
+        for value in values.iter().duplicates() {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                DiagnosticCode::DuplicateEntry,
+                key,
+                format!("`{}` lists `{}` more than once", key, value),
+            ));
+        }
+    }
+
+    let important_rules = config.important_rules.as_deref().unwrap_or_default();
+    for rule in config.ignore_rules.iter().flatten().unique() {
+        if important_rules.contains(rule) {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                DiagnosticCode::RuleImportantAndIgnored,
+                "ignoreRules",
+                format!(
+                    "`{}` is in both `importantRules` and `ignoreRules`, so it is ignored",
+                    rule
+                ),
+            ));
+        }
+    }
+
+    let disable_tools = config.disable_tools.as_deref().unwrap_or_default();
+    let enabled_tools = config.tools.iter().chain(&config.custom_tools).flatten();
+    for tool in enabled_tools.unique_by(|tool| tool.to_ascii_lowercase()) {
+        if disable_tools.iter().any(|t| t.eq_ignore_ascii_case(tool)) {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                DiagnosticCode::ToolEnabledAndDisabled,
+                "disableTools",
+                format!(
+                    "`{}` is enabled but also in `disableTools`, so it is disabled",
+                    tool
+                ),
+            ));
+        }
+    }
+
+    let registry = ToolRegistry::lift();
+    for tool in config.custom_tools.iter().flatten() {
+        if registry.get(tool).is_some() {
+            diagnostics.push(Diagnostic::new(
+                Severity::Info,
+                DiagnosticCode::CustomToolIsBuiltin,
+                "customTools",
+                format!(
+                    "`{}` is a built-in tool, list it in `tools` instead of `customTools`",
+                    tool
+                ),
+            ));
+        }
+    }
+
+    if let Some(android_version) = config.android_version {
+        if !ANDROID_VERSIONS.contains(&android_version) {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                DiagnosticCode::AndroidVersionOutOfRange,
+                "androidVersion",
+                format!(
+                    "`androidVersion` is {}, but must be an Android API level from {} to {}",
+                    android_version,
+                    ANDROID_VERSIONS.start(),
+                    ANDROID_VERSIONS.end()
+                ),
+            ));
+        }
+    }
+
+    // Sorting is stable, so the diagnostics about each key stay in the order they were found.
+    diagnostics.sort_by_key(|diagnostic| KEYS.iter().position(|key| *key == diagnostic.key));
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(config: &Config) -> Vec<(Severity, DiagnosticCode, &'static str)> {
+        validate(config)
+            .into_iter()
+            .map(|d| (d.severity, d.code, d.key))
+            .collect()
+    }
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|value| String::from(*value)).collect())
+    }
+
+    #[test]
+    fn it_accepts_a_sensible_config() {
+        let config = Config {
+            build: Some(String::from("make")),
+            tools: strings(&["infer", "eslint"]),
+            custom_tools: strings(&["my-linter"]),
+            important_rules: strings(&["NULL_DEREFERENCE"]),
+            ignore_rules: strings(&["RESOURCE_LEAK"]),
+            android_version: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(validate(&config), vec![]);
+    }
+
+    #[test]
+    fn it_rejects_android_versions_outside_the_range() {
+        for android_version in [0, 9999] {
+            let config = Config {
+                android_version: Some(android_version),
+                ..Default::default()
+            };
+            assert_eq!(
+                codes(&config),
+                vec![(
+                    Severity::Error,
+                    DiagnosticCode::AndroidVersionOutOfRange,
+                    "androidVersion"
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn it_warns_about_values_that_do_not_do_what_they_say() {
+        let config = Config {
+            build: Some(String::from("  ")),
+            tools: strings(&["infer", "infer", "ESLint"]),
+            disable_tools: strings(&["eslint"]),
+            custom_tools: strings(&["infer"]),
+            important_rules: strings(&["NULL_DEREFERENCE"]),
+            ignore_rules: strings(&["NULL_DEREFERENCE"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            codes(&config),
+            vec![
+                (Severity::Error, DiagnosticCode::EmptyCommand, "build"),
+                (
+                    Severity::Warning,
+                    DiagnosticCode::RuleImportantAndIgnored,
+                    "ignoreRules"
+                ),
+                (Severity::Warning, DiagnosticCode::DuplicateEntry, "tools"),
+                (
+                    Severity::Warning,
+                    DiagnosticCode::ToolEnabledAndDisabled,
+                    "disableTools"
+                ),
+                (
+                    Severity::Info,
+                    DiagnosticCode::CustomToolIsBuiltin,
+                    "customTools"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_describes_diagnostics_with_their_code() {
+        let config = Config {
+            android_version: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate(&config)[0].to_string(),
+            "error[android-version-out-of-range]: `androidVersion` is 0, but must be an Android API level from 1 to 36"
+        );
+    }
+}